@@ -0,0 +1,220 @@
+use std::fmt::{self, Display, Formatter};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Table of opening brackets and the closing bracket for each.
+pub struct Brackets {
+    pairs: Vec<(char, char)>,
+}
+
+impl Brackets {
+    /// Create a table from its pairs. Returns `None` if any character
+    /// is used more than once, including a pair that opens and closes
+    /// with the same character, since it couldn't be told which it is.
+    pub fn new(pairs: &[(char, char)]) -> Option<Self> {
+        let mut seen = vec![];
+        for &(open, close) in pairs {
+            for c in [open, close] {
+                if seen.contains(&c) {
+                    return None;
+                }
+                seen.push(c);
+            }
+        }
+        Some(Self {
+            pairs: pairs.to_vec(),
+        })
+    }
+
+    /// Parentheses along with square, curly, and angle brackets.
+    pub fn standard() -> Self {
+        Self::new(&[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')]).unwrap()
+    }
+
+    /// Closing bracket for an opening bracket.
+    pub fn closer(&self, open: char) -> Option<char> {
+        self.pairs
+            .iter()
+            .find(|&&(lhs, _)| lhs == open)
+            .map(|&(_, rhs)| rhs)
+    }
+
+    /// Is this a closing bracket?
+    pub fn is_closer(&self, c: char) -> bool {
+        self.pairs.iter().any(|&(_, rhs)| rhs == c)
+    }
+
+    /// Check that every bracket on the line is closed by the matching
+    /// bracket, stopping at the first problem.
+    pub fn check(&self, line: &str) -> Diagnostic {
+        let mut stack = vec![];
+        for (column, found) in line.chars().enumerate() {
+            if let Some(close) = self.closer(found) {
+                stack.push(close);
+                continue;
+            }
+
+            if !self.is_closer(found) {
+                return Diagnostic::Unknown { column, found };
+            }
+
+            let expected = stack.pop();
+            if expected != Some(found) {
+                return Diagnostic::Corrupted {
+                    column,
+                    expected,
+                    found,
+                };
+            }
+        }
+
+        if stack.is_empty() {
+            return Diagnostic::Valid;
+        }
+        Diagnostic::Incomplete {
+            completion: stack.iter().rev().collect(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Result of checking a line. Columns count characters from zero.
+pub enum Diagnostic {
+    /// Every bracket is closed.
+    Valid,
+    /// A closing bracket doesn't match the last open bracket.
+    Corrupted {
+        /// Column of the closing bracket.
+        column: usize,
+        /// Bracket that would close the last open bracket, if any.
+        expected: Option<char>,
+        /// Bracket that was found instead.
+        found: char,
+    },
+    /// Some brackets are never closed.
+    Incomplete {
+        /// Closing brackets needed to complete the line.
+        completion: String,
+    },
+    /// A character that isn't in the bracket table.
+    Unknown {
+        /// Column of the character.
+        column: usize,
+        /// The character.
+        found: char,
+    },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Diagnostic::Valid => write!(f, "valid"),
+            Diagnostic::Corrupted {
+                column,
+                expected: Some(expected),
+                found,
+            } => write!(
+                f,
+                "column {}: expected {}, but found {} instead",
+                column + 1,
+                expected,
+                found
+            ),
+            Diagnostic::Corrupted {
+                column,
+                expected: None,
+                found,
+            } => write!(f, "column {}: nothing to close with {}", column + 1, found),
+            Diagnostic::Incomplete { completion } => {
+                write!(f, "incomplete: complete by adding {}", completion)
+            }
+            Diagnostic::Unknown { column, found } => {
+                write!(f, "column {}: {:?} isn't a bracket", column + 1, found)
+            }
+        }
+    }
+}
+
+/// Rules for scoring diagnostics.
+pub trait Score {
+    /// Score the diagnostic, or `None` if these rules don't apply to it.
+    fn score(&self, diagnostic: &Diagnostic) -> Option<u64>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ambiguous_pairs() {
+        assert_eq!(Brackets::new(&[('|', '|')]), None);
+        assert_eq!(Brackets::new(&[('(', ')'), ('[', ')')]), None);
+        assert_eq!(Brackets::new(&[('(', ')'), (')', '(')]), None);
+        assert!(Brackets::new(&[('«', '»'), ('(', ')')]).is_some());
+    }
+
+    #[test]
+    fn positions() {
+        let brackets = Brackets::new(&[('«', '»'), ('(', ')')]).unwrap();
+        assert_eq!(brackets.check("«()»"), Diagnostic::Valid);
+        assert_eq!(
+            brackets.check("«(»)"),
+            Diagnostic::Corrupted {
+                column: 2,
+                expected: Some(')'),
+                found: '»',
+            }
+        );
+        assert_eq!(
+            brackets.check("()»"),
+            Diagnostic::Corrupted {
+                column: 2,
+                expected: None,
+                found: '»',
+            }
+        );
+        assert_eq!(
+            brackets.check("«(x"),
+            Diagnostic::Unknown {
+                column: 2,
+                found: 'x',
+            }
+        );
+        assert_eq!(
+            brackets.check("««()("),
+            Diagnostic::Incomplete {
+                completion: ")»»".to_string(),
+            }
+        );
+        assert_eq!(
+            brackets.check("«(»)").to_string(),
+            "column 3: expected ), but found » instead"
+        );
+        assert_eq!(
+            brackets.check("()»").to_string(),
+            "column 3: nothing to close with »"
+        );
+    }
+
+    /// Counts how deeply nested an incomplete line is left.
+    struct Depth;
+
+    impl Score for Depth {
+        fn score(&self, diagnostic: &Diagnostic) -> Option<u64> {
+            match diagnostic {
+                Diagnostic::Valid => Some(0),
+                Diagnostic::Incomplete { completion } => Some(completion.chars().count() as u64),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn custom_score() {
+        let brackets = Brackets::standard();
+        let scores = ["()", "([{", "(]", "<a>"]
+            .iter()
+            .map(|line| Depth.score(&brackets.check(line)))
+            .collect::<Vec<_>>();
+        assert_eq!(scores, [Some(0), Some(3), None, None]);
+    }
+}
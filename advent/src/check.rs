@@ -8,7 +8,7 @@ extern crate day;
 /// In a file named `path/to/day00.rs`, the following invocation:
 ///
 /// ```rust,no_run
-/// advent::check!(ex 1 = "hello", part 1 = "world", ex 1 = DIFF);
+/// advent::check!(ex 1 = "hello", part 1 = "world", ex 1 = DIFF, part 2 = OCR "HI");
 /// ```
 ///
 /// expands to
@@ -43,6 +43,16 @@ extern crate day;
 ///             println!("\n{}", result);
 ///         }
 ///     }
+///
+///     #[test]
+///     pub fn ocr_part2() {
+///         let parsed = crate::check::parse_lines(INPUT);
+///         let result = super::part2(&parsed).to_string();
+///         match crate::ocr::read_text(&result) {
+///             Ok(text) => assert_eq!(text, "HI", "\n{}", result),
+///             Err(err) => panic!("\nResult:\n{}\ncould not be read: {}\n", result, err),
+///         }
+///     }
 /// }
 /// ```
 macro_rules! check {
@@ -98,6 +108,38 @@ macro_rules! gen {
         gen!($($tail)+);
     };
 
+    (ocr, $input:ident, $prefix:ident, $n:literal, $expected:literal) => {
+        paste::paste! {
+            #[test]
+            pub fn [<ocr_ $prefix $n>]() {
+                let parsed = crate::check::parse_lines($input);
+                let result = super::[<part $n>](&parsed).to_string();
+                match crate::ocr::read_text(&result) {
+                    Ok(text) => assert_eq!(text, $expected, "\n{}", result),
+                    Err(err) => panic!("\nResult:\n{}\ncould not be read: {}\n", result, err),
+                }
+            }
+        }
+    };
+
+    (ex $n:literal = OCR $expected:literal) => {
+        gen!(ocr, EXAMPLE, example, $n, $expected);
+    };
+
+    (ex $n:literal = OCR $expected:literal, $($tail:tt)+) => {
+        gen!(ex $n = OCR $expected);
+        gen!($($tail)+);
+    };
+
+    (part $n:literal = OCR $expected:literal) => {
+        gen!(ocr, INPUT, part, $n, $expected);
+    };
+
+    (part $n:literal = OCR $expected:literal, $($tail:tt)+) => {
+        gen!(part $n = OCR $expected);
+        gen!($($tail)+);
+    };
+
     (ex $n:literal = $expected:expr) => {
         gen!(test, EXAMPLE, example, $n, $expected);
     };
@@ -0,0 +1,202 @@
+use std::ops::RangeInclusive;
+
+/// Add some interval-like functionality to a type
+pub trait Interval: Sized {
+    /// Result of intersect
+    type Intersection;
+
+    /// Length of interval.
+    fn length(&self) -> usize;
+
+    /// Compute the intersection of an interval
+    fn intersect(&self, other: &Self) -> Self::Intersection;
+
+    /// Does this interval contain every point of the other interval?
+    fn covers(&self, other: &Self) -> bool;
+
+    /// Parts of this interval before and after the other interval,
+    /// if they're non-empty.
+    fn outside(&self, other: &Self) -> (Option<Self>, Option<Self>);
+}
+
+impl Interval for RangeInclusive<i64> {
+    type Intersection = Option<Self>;
+
+    fn length(&self) -> usize {
+        if self.end() < self.start() {
+            return 0;
+        }
+        (self.end() - self.start() + 1) as usize
+    }
+
+    fn intersect(&self, other: &Self) -> Self::Intersection {
+        if other.end() < self.start() || self.end() < other.start() {
+            return None;
+        }
+        let start = *self.start().max(other.start());
+        let end = *self.end().min(other.end());
+        Some(start..=end)
+    }
+
+    fn covers(&self, other: &Self) -> bool {
+        self.start() <= other.start() && other.end() <= self.end()
+    }
+
+    fn outside(&self, other: &Self) -> (Option<Self>, Option<Self>) {
+        let before = *self.start()..=(*self.end()).min(other.start() - 1);
+        let after = (*self.start()).max(other.end() + 1)..=*self.end();
+        let nonempty = |range: Self| if range.is_empty() { None } else { Some(range) };
+        (nonempty(before), nonempty(after))
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+/// Axis-aligned box in `D` dimensions with inclusive integer sides.
+pub struct Cuboid<const D: usize> {
+    sides: [RangeInclusive<i64>; D],
+}
+
+impl<const D: usize> Cuboid<D> {
+    /// Create a box from its side along each axis.
+    pub fn new(sides: [RangeInclusive<i64>; D]) -> Self {
+        Self { sides }
+    }
+
+    /// Create a box with equal-length sides.
+    pub fn cube(side: RangeInclusive<i64>) -> Self {
+        Self::new([(); D].map(|_| side.clone()))
+    }
+
+    /// Side of the box along an axis.
+    pub fn side(&self, axis: usize) -> &RangeInclusive<i64> {
+        &self.sides[axis]
+    }
+
+    /// Number of points in the box.
+    pub fn volume(&self) -> usize {
+        self.sides.iter().map(Interval::length).product()
+    }
+
+    /// Does the box contain this point?
+    pub fn contains(&self, point: [i64; D]) -> bool {
+        self.sides
+            .iter()
+            .zip(point)
+            .all(|(side, x)| side.contains(&x))
+    }
+
+    /// Does this box contain every point of the other box?
+    pub fn covers(&self, other: &Self) -> bool {
+        self.sides
+            .iter()
+            .zip(&other.sides)
+            .all(|(lhs, rhs)| lhs.covers(rhs))
+    }
+
+    /// Do the boxes share any points?
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.sides
+            .iter()
+            .zip(&other.sides)
+            .all(|(lhs, rhs)| lhs.start() <= rhs.end() && rhs.start() <= lhs.end())
+    }
+
+    /// Compute the intersection of two boxes if it exists.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let mut sides = self.sides.clone();
+        for (side, other) in sides.iter_mut().zip(&other.sides) {
+            *side = side.intersect(other)?;
+        }
+        Some(Self { sides })
+    }
+
+    /// Split the parts of this box outside of `other` into at most
+    /// `2 * D` disjoint boxes. Slabs are cut off along the first axis,
+    /// then along the second axis within the overlap on the first
+    /// axis, and so on.
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let overlap = match self.intersect(other) {
+            Some(overlap) => overlap,
+            None => return vec![self.clone()],
+        };
+
+        let mut pieces = vec![];
+        let mut rest = self.clone();
+        for axis in 0..D {
+            let (before, after) = self.sides[axis].outside(&overlap.sides[axis]);
+            for side in before.into_iter().chain(after) {
+                let mut piece = rest.clone();
+                piece.sides[axis] = side;
+                pieces.push(piece);
+            }
+            rest.sides[axis] = overlap.sides[axis].clone();
+        }
+        pieces
+    }
+
+    /// Remove `hole` from every box, keeping boxes that miss it as
+    /// they are rather than splitting them.
+    pub fn carve(boxes: Vec<Self>, hole: &Self) -> Vec<Self> {
+        let mut carved = Vec::with_capacity(boxes.len());
+        for b in boxes {
+            if b.overlaps(hole) {
+                carved.extend(b.subtract(hole));
+            } else {
+                carved.push(b);
+            }
+        }
+        carved
+    }
+
+    /// Number of points covered by any of the boxes.
+    pub fn union_volume(boxes: &[Self]) -> usize {
+        let mut disjoint: Vec<Self> = vec![];
+        for b in boxes {
+            disjoint = Self::carve(disjoint, b);
+            disjoint.push(b.clone());
+        }
+        disjoint.iter().map(Self::volume).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn inverted_sides() {
+        assert_eq!(RangeInclusive::new(3, 1).length(), 0);
+        assert_eq!((-2..=2).length(), 5);
+        assert_eq!(Cuboid::new([0..=4, RangeInclusive::new(2, 1)]).volume(), 0);
+    }
+
+    #[test]
+    fn subtract() {
+        let outer = Cuboid::<3>::cube(0..=2);
+        assert_eq!(outer.subtract(&Cuboid::cube(5..=6)), vec![outer.clone()]);
+        assert!(outer.subtract(&Cuboid::cube(-1..=3)).is_empty());
+
+        let pieces = outer.subtract(&Cuboid::cube(1..=1));
+        assert_eq!(pieces.len(), 6);
+        assert_eq!(pieces.iter().map(Cuboid::volume).sum::<usize>(), 26);
+        assert!(pieces
+            .iter()
+            .tuple_combinations()
+            .all(|(a, b)| !a.overlaps(b)));
+
+        let pieces = Cuboid::new([0..=3, 0..=0]).subtract(&Cuboid::new([2..=5, -1..=1]));
+        assert_eq!(pieces, vec![Cuboid::new([0..=1, 0..=0])]);
+    }
+
+    #[test]
+    fn union_volume() {
+        let boxes = [
+            Cuboid::new([0..=9, 0..=9]),
+            Cuboid::new([5..=14, 5..=14]),
+            Cuboid::new([20..=20, 20..=29]),
+        ];
+        assert_eq!(Cuboid::union_volume(&boxes), 100 + 100 - 25 + 10);
+        assert_eq!(Cuboid::<2>::union_volume(&[]), 0);
+    }
+}
@@ -1,37 +1,20 @@
-use std::convert::TryInto;
+use crate::grid::{Grid, Pos};
 use std::fmt::{self, Display, Formatter};
 
 /// Find the first board that can win.
 ///
 /// The result is the board score × the last number picked.
 pub fn part1(input: &[String]) -> i32 {
-    let picks = parse_numbers(&input[0]);
-    let mut boards = parse_boards(&input[1..]);
-    for n in picks {
-        for board in boards.iter_mut() {
-            if board.mark(n) {
-                return board.score();
-            }
-        }
-    }
-    0
+    let mut game = Game::parse(input, Rules::STANDARD);
+    game.play().first().map_or(0, |win| win.score)
 }
 
 /// Find the last board that can win.
 ///
 /// The result is the board score × the last number picked.
 pub fn part2(input: &[String]) -> i32 {
-    let picks = parse_numbers(&input[0]);
-    let mut boards = parse_boards(&input[1..]);
-    let mut last_score = 0;
-    for n in picks {
-        for board in boards.iter_mut() {
-            if board.mark(n) {
-                last_score = board.score();
-            }
-        }
-    }
-    last_score
+    let mut game = Game::parse(input, Rules::STANDARD);
+    game.play().last().map_or(0, |win| win.score)
 }
 
 /// Parse "randomly" chosen numbers.
@@ -39,34 +22,128 @@ pub fn parse_numbers(line: &str) -> Vec<i32> {
     line.split(',').flat_map(str::parse).collect()
 }
 
-/// Parse sequence of boards.
+/// Parse sequence of boards separated by blank lines.
 pub fn parse_boards(lines: &[String]) -> Vec<Board> {
     lines
-        .chunks(6)
-        .map(|board| {
-            let board: Vec<String> = board.iter().skip(1).cloned().collect();
-            parse_board(&board)
-        })
+        .split(|line| line.trim().is_empty())
+        .filter(|board| !board.is_empty())
+        .map(parse_board)
         .collect()
 }
 
-/// Parse a single board.
+/// Parse a single board, one row per line.
+///
+/// # Panics
+///
+/// Panics if the rows aren't all the same length.
 pub fn parse_board(lines: &[String]) -> Board {
-    let squares = lines
+    let rows: Vec<Vec<i32>> = lines
         .iter()
-        .flat_map(|line| line.split_whitespace())
-        .flat_map(str::parse)
+        .map(|line| line.split_whitespace().flat_map(str::parse).collect())
         .collect();
-    Board::new(squares)
+    let width = rows.first().map_or(0, Vec::len);
+    let height = rows.len();
+    let squares = rows.into_iter().flatten().collect();
+    Board::new(Grid::new(width, height, squares))
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Which lines of marked squares win.
+pub struct Rules {
+    /// Any complete row wins.
+    pub rows: bool,
+    /// Any complete column wins.
+    pub columns: bool,
+    /// Either complete diagonal of a square board wins.
+    pub diagonals: bool,
+}
+
+impl Rules {
+    /// Rows and columns win, but diagonals don't count.
+    pub const STANDARD: Rules = Rules {
+        rows: true,
+        columns: true,
+        diagonals: false,
+    };
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// A board winning on one of the calls.
+pub struct Win {
+    /// Index of the board.
+    pub board: usize,
+    /// Index of the call, starting at 0.
+    pub call: usize,
+    /// Number called.
+    pub number: i32,
+    /// The board's score.
+    pub score: i32,
+}
+
+impl Display for Win {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "Board {} wins on call {} ({}) with score {}",
+            self.board + 1,
+            self.call + 1,
+            self.number,
+            self.score
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A game of Bingo: the numbers to call and the boards playing.
+pub struct Game {
+    picks: Vec<i32>,
+    boards: Vec<Board>,
+    rules: Rules,
+}
+
+impl Game {
+    /// Parse the picks from the first line and the boards from the rest.
+    pub fn parse(input: &[String], rules: Rules) -> Self {
+        Self {
+            picks: parse_numbers(&input[0]),
+            boards: parse_boards(&input[1..]),
+            rules,
+        }
+    }
+
+    /// The boards playing.
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// Call every number, returning each win in order. Boards that win
+    /// on the same call are listed in board order, and a board stops
+    /// playing once it wins.
+    pub fn play(&mut self) -> Vec<Win> {
+        let mut log = vec![];
+        for (call, &number) in self.picks.iter().enumerate() {
+            for (i, board) in self.boards.iter_mut().enumerate() {
+                if board.mark(number, &self.rules) {
+                    log.push(Win {
+                        board: i,
+                        call,
+                        number,
+                        score: board.score(),
+                    });
+                }
+            }
+        }
+        log
+    }
 }
 
-#[derive(PartialEq, Eq, Debug)]
-/// A 5 × 5 Bingo board that tracks its own state
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A rectangular Bingo board that tracks its own state
 pub struct Board {
-    /// Flat array of squares
-    squares: [i32; 25],
-    /// Bitmask indicating which squares are marked
-    marked: u32,
+    /// Numbers on the board
+    squares: Grid<i32>,
+    /// Which squares are marked
+    marked: Grid<bool>,
     /// Value of last square to be marked
     last: i32,
     /// Whether this board has already won
@@ -74,57 +151,64 @@ pub struct Board {
 }
 
 impl Board {
-    /// Create a new board from a vector of 25 numbers.
-    ///
-    /// # Panics
-    ///
-    /// Panics if not passed exactly 25 numbers.
-    pub fn new(squares: Vec<i32>) -> Self {
-        let squares = squares.try_into().expect("25 elements");
+    /// Create a new board from its squares.
+    pub fn new(squares: Grid<i32>) -> Self {
+        let marked = squares.map(|_| false);
         Self {
             squares,
-            marked: 0,
+            marked,
             last: 0,
             won: false,
         }
     }
 
+    /// Has this board already won?
+    pub fn won(&self) -> bool {
+        self.won
+    }
+
     /// Mark the square if this board has it and return whether this turn wins
-    pub fn mark(&mut self, n: i32) -> bool {
+    pub fn mark(&mut self, n: i32, rules: &Rules) -> bool {
         if self.won {
             return false;
         }
 
-        let mut check = false;
-        for (i, &square) in self.squares.iter().rev().enumerate() {
-            if square == n {
-                self.marked |= 1 << i;
-                self.last = n;
-                check = true;
-            }
+        let hits: Vec<Pos> = self
+            .squares
+            .iter()
+            .filter(|&(_, &square)| square == n)
+            .map(|(pos, _)| pos)
+            .collect();
+        for &pos in &hits {
+            self.marked[pos] = true;
+            self.last = n;
         }
 
-        self.won = check
-            && WINNING_MASKS
-                .iter()
-                .any(|&mask| (mask & self.marked) == mask);
+        self.won = hits.iter().any(|&pos| self.completes(pos, rules));
         self.won
     }
 
+    /// Does the square complete a winning line?
+    fn completes(&self, (row, col): Pos, rules: &Rules) -> bool {
+        let (width, height) = (self.squares.width(), self.squares.height());
+        let marked = |pos: Pos| self.marked[pos];
+        let square = width == height;
+        (rules.rows && (0..width).all(|c| marked((row, c))))
+            || (rules.columns && (0..height).all(|r| marked((r, col))))
+            || (rules.diagonals && square && row == col && (0..width).all(|i| marked((i, i))))
+            || (rules.diagonals
+                && square
+                && row + col + 1 == width
+                && (0..width).all(|i| marked((i, width - 1 - i))))
+    }
+
     /// Calculate score as sum of unmarked numbers × last number marked
     pub fn score(&self) -> i32 {
         let unused: i32 = self
             .squares
             .iter()
-            .rev()
-            .enumerate()
-            .filter_map(|(i, &square)| {
-                if self.marked & (1 << i) == 0 {
-                    Some(square)
-                } else {
-                    None
-                }
-            })
+            .filter(|&(pos, _)| !self.marked[pos])
+            .map(|(_, &square)| square)
             .sum();
         self.last * unused
     }
@@ -132,33 +216,90 @@ impl Board {
 
 impl Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        for i in 0..5 {
-            for j in 0..5 {
-                if j == 0 {
-                    write!(f, "{:>2}", self.squares[i * 5 + j])?;
-                } else {
-                    write!(f, ", {:>2}", self.squares[i * 5 + j])?;
-                }
+        for ((_, col), square) in self.squares.iter() {
+            if col == 0 {
+                write!(f, "{:>2}", square)?;
+            } else {
+                write!(f, ", {:>2}", square)?;
+            }
+            if col + 1 == self.squares.width() {
+                writeln!(f)?;
             }
-            writeln!(f)?;
         }
         Ok(())
     }
 }
 
-/// Bit patterns of winning boards.
-pub static WINNING_MASKS: [u32; 10] = [
-    0b1111100000000000000000000, // row 1
-    0b0000011111000000000000000, // row 2
-    0b0000000000111110000000000, // row 3
-    0b0000000000000001111100000, // row 4
-    0b0000000000000000000011111, // row 5
-    0b1000010000100001000010000, // col 1
-    0b0100001000010000100001000, // col 2
-    0b0010000100001000010000100, // col 3
-    0b0001000010000100001000010, // col 4
-    0b0000100001000010000100001, // col 5
-];
-
 check!(ex 1 = 4512, ex 2 = 1924, part 1 = 8442, part 2 = 4590);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    const GAME: &str = "\
+1,5,9,2,3,4
+
+1 2 3
+4 5 6
+7 8 9
+
+1 2 3 4
+5 6 7 8";
+
+    fn win(board: usize, call: usize, number: i32, score: i32) -> Win {
+        Win {
+            board,
+            call,
+            number,
+            score,
+        }
+    }
+
+    #[test]
+    fn uneven_boards() {
+        let mut game = Game::parse(&crate::check::parse_lines(GAME), Rules::STANDARD);
+        let sizes = game
+            .boards()
+            .iter()
+            .map(|board| (board.squares.width(), board.squares.height()))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [(3, 3), (4, 2)]);
+        let log = game.play();
+        assert_eq!(log, [win(1, 1, 5, 150), win(0, 4, 3, 75)]);
+        assert!(game.boards().iter().all(Board::won));
+        assert_eq!(
+            log[0].to_string(),
+            "Board 2 wins on call 2 (5) with score 150"
+        );
+    }
+
+    #[test]
+    fn diagonals() {
+        let rules = Rules {
+            rows: false,
+            columns: false,
+            diagonals: true,
+        };
+        let mut game = Game::parse(&crate::check::parse_lines(GAME), rules);
+        assert_eq!(game.play(), [win(0, 2, 9, 270)]);
+
+        let lines = crate::check::parse_lines(&GAME.replacen("1,5,9", "3,5,7", 1));
+        let mut game = Game::parse(&lines, rules);
+        assert_eq!(game.play(), [win(0, 2, 7, 7 * 30)]);
+    }
+
+    #[test]
+    fn example_log() {
+        let mut game = Game::parse(&crate::check::parse_lines(tests::EXAMPLE), Rules::STANDARD);
+        let log = game.play();
+        assert_eq!(
+            log,
+            [
+                win(2, 11, 24, 4512),
+                win(0, 13, 16, 2192),
+                win(1, 14, 13, 1924)
+            ]
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use crate::cuboid::Cuboid;
+use crate::grid::{Grid, Pos};
 use itertools::Itertools;
 use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
 use std::str::FromStr;
 
 /// Count the number of points where horizontal or vertical lines overlap
@@ -19,14 +22,172 @@ pub fn count_overlapping<F>(lines: &[Line], pred: F) -> usize
 where
     F: Fn(&Line) -> bool,
 {
-    lines
+    let lines = lines
         .iter()
-        .filter_map(|&line| if pred(&line) { Some(line) } else { None })
-        .flat_map(|line| line.points())
-        .counts()
-        .into_values()
-        .filter(|&n| n > 1)
-        .count()
+        .filter(|line| pred(line))
+        .copied()
+        .collect_vec();
+    Density::new(&lines).overlapping()
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// How to export a heatmap.
+pub enum Format {
+    /// Each line followed by a diagram like the puzzle's.
+    Text,
+    /// Grayscale plain PGM image.
+    Pgm,
+    /// Color plain PPM image.
+    Ppm,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Number of lines covering each point within the bounds of the lines.
+pub struct Density {
+    origin: Point,
+    counts: Grid<u32>,
+}
+
+impl Density {
+    /// Rasterize every line onto a grid just large enough to hold them.
+    pub fn new(lines: &[Line]) -> Self {
+        let bounds = lines.iter().map(Line::bounds);
+        let (left, top, right, bottom) = bounds.fold(
+            (i64::MAX, i64::MAX, i64::MIN, i64::MIN),
+            |(left, top, right, bottom), bounds| {
+                let (x, y) = (bounds.side(0), bounds.side(1));
+                (
+                    left.min(*x.start()),
+                    top.min(*y.start()),
+                    right.max(*x.end()),
+                    bottom.max(*y.end()),
+                )
+            },
+        );
+        let (origin, width, height) = if lines.is_empty() {
+            (Point::new(0, 0), 0, 0)
+        } else {
+            let origin = Point::new(left as i32, top as i32);
+            (
+                origin,
+                (right - left + 1) as usize,
+                (bottom - top + 1) as usize,
+            )
+        };
+
+        let mut density = Self {
+            origin,
+            counts: Grid::filled(width, height, 0),
+        };
+        for line in lines {
+            for point in line.points() {
+                let pos = density.pos(point);
+                density.counts[pos] += 1;
+            }
+        }
+        density
+    }
+
+    /// Top-left corner of the grid.
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Number of lines covering the point.
+    pub fn get(&self, point: Point) -> u32 {
+        let (row, col) = (point.y - self.origin.y, point.x - self.origin.x);
+        if row < 0 || col < 0 {
+            return 0;
+        }
+        self.counts
+            .get((row as usize, col as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Count of each point relative to the origin.
+    pub fn counts(&self) -> &Grid<u32> {
+        &self.counts
+    }
+
+    /// Number of points covered by more than one line.
+    pub fn overlapping(&self) -> usize {
+        self.counts.cells().iter().filter(|&&n| n > 1).count()
+    }
+
+    /// Most lines covering any one point.
+    pub fn max(&self) -> u32 {
+        self.counts.cells().iter().copied().max().unwrap_or(0)
+    }
+
+    /// Export the density as a heatmap. Each line is listed first, as a
+    /// comment in the image formats.
+    pub fn write<W: Write>(&self, lines: &[Line], format: Format, mut out: W) -> io::Result<()> {
+        let (width, height) = (self.counts.width(), self.counts.height());
+        let max = self.max().clamp(1, u16::MAX as u32);
+        let magic = match format {
+            Format::Text => None,
+            Format::Pgm => Some("P2"),
+            Format::Ppm => Some("P3"),
+        };
+        if let Some(magic) = magic {
+            writeln!(out, "{}", magic)?;
+        }
+        writeln!(out, "# origin {}", self.origin)?;
+        for line in lines {
+            writeln!(out, "# {}", line)?;
+        }
+
+        match format {
+            Format::Text => writeln!(out, "{}", self),
+            Format::Pgm => {
+                writeln!(out, "{} {}\n{}", width, height, max)?;
+                for row in self.counts.cells().chunks(width.max(1)) {
+                    writeln!(out, "{}", row.iter().map(|&n| n.min(max)).join(" "))?;
+                }
+                Ok(())
+            }
+            Format::Ppm => {
+                writeln!(out, "{} {}\n255", width, height)?;
+                for row in self.counts.cells().chunks(width.max(1)) {
+                    let mut pixels = row.iter().map(|&n| {
+                        let (r, g, b) = heat(n.min(max) as f64 / max as f64);
+                        format!("{} {} {}", r, g, b)
+                    });
+                    writeln!(out, "{}", pixels.join("  "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Position of the point in the grid.
+    fn pos(&self, point: Point) -> Pos {
+        (
+            (point.y - self.origin.y) as usize,
+            (point.x - self.origin.x) as usize,
+        )
+    }
+}
+
+impl Display for Density {
+    /// Draw the density like the puzzle's diagrams: `.` where there are
+    /// no lines, otherwise the count, or `+` beyond 9.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let tiles = self.counts.map(|&n| match n {
+            0 => '.',
+            1..=9 => char::from_digit(n, 10).unwrap(),
+            _ => '+',
+        });
+        write!(f, "{}", tiles)
+    }
+}
+
+/// Map a fraction of the maximum to a color, running from black
+/// through red and yellow to white.
+fn heat(t: f64) -> (u8, u8, u8) {
+    let channel = |offset: f64| ((t * 3.0 - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+    (channel(0.0), channel(1.0), channel(2.0))
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
@@ -63,29 +224,48 @@ impl Line {
         (self.lhs.x - self.rhs.x).abs() == (self.lhs.y - self.rhs.y).abs()
     }
 
-    /// Return sequence of points covered by a horizontal, vertical, or
-    /// 45° diagonal line.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the line is not a horizontal, vertical, or 45°
-    /// diagonal line. For any other kind of line, we would have to
-    /// interpolate points _à la_ [Bresenham's line algorithm][Bresenham].
+    /// Smallest box containing both endpoints.
+    pub fn bounds(&self) -> Cuboid<2> {
+        let (x0, x1) = (self.lhs.x.min(self.rhs.x), self.lhs.x.max(self.rhs.x));
+        let (y0, y1) = (self.lhs.y.min(self.rhs.y), self.lhs.y.max(self.rhs.y));
+        Cuboid::new([x0 as i64..=x1 as i64, y0 as i64..=y1 as i64])
+    }
+
+    /// Return sequence of points covered by the line, from `lhs` to
+    /// `rhs`. Lines at any angle are rasterized with
+    /// [Bresenham's line algorithm][Bresenham], which covers exactly
+    /// the expected points for horizontal, vertical, and 45° diagonal
+    /// lines.
     ///
     /// [Bresenham]: https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm
     pub fn points(&self) -> impl Iterator<Item = Point> {
-        assert!(self.is_horizontal() || self.is_vertical() || self.is_diagonal());
-
-        let x_dist = self.rhs.x - self.lhs.x;
-        let y_dist = self.rhs.y - self.lhs.y;
+        let (end, mut x, mut y) = (self.rhs, self.lhs.x, self.lhs.y);
+        let dx = (end.x - x).abs();
+        let dy = -(end.y - y).abs();
+        let (sx, sy) = ((end.x - x).signum(), (end.y - y).signum());
+        let mut err = dx + dy;
+        let mut done = false;
 
-        let dx = x_dist.signum();
-        let dy = y_dist.signum();
-
-        let (x, y) = (self.lhs.x, self.lhs.y);
-
-        let n = usize::max(x_dist.abs() as usize, y_dist.abs() as usize);
-        (0..=n).map(move |i| Point::new(x + dx * i as i32, y + dy * i as i32))
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let point = Point::new(x, y);
+            if point == end {
+                done = true;
+            } else {
+                let e2 = 2 * err;
+                if e2 >= dy {
+                    err += dy;
+                    x += sx;
+                }
+                if e2 <= dx {
+                    err += dx;
+                    y += sy;
+                }
+            }
+            Some(point)
+        })
     }
 }
 
@@ -139,3 +319,61 @@ impl Display for Point {
 
 check!(ex 1 = 5, ex 2 = 12, part 1 = 5632, part 2 = 22213);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    fn export(format: Format) -> String {
+        let lines = ["1,1 -> 3,1", "2,0 -> 2,2"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect::<Vec<Line>>();
+        let density = Density::new(&lines);
+        let mut out = vec![];
+        density.write(&lines, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const HEADER: &str = "# origin 1,0\n# 1,1 -> 3,1\n# 2,0 -> 2,2\n";
+
+    #[test]
+    fn text() {
+        assert_eq!(export(Format::Text), format!("{}.1.\n121\n.1.\n", HEADER));
+    }
+
+    #[test]
+    fn pgm() {
+        let body = "3 3\n2\n0 1 0\n1 2 1\n0 1 0\n";
+        assert_eq!(export(Format::Pgm), format!("P2\n{}{}", HEADER, body));
+    }
+
+    #[test]
+    fn ppm() {
+        let body = "\
+3 3
+255
+0 0 0  255 128 0  0 0 0
+255 128 0  255 255 255  255 128 0
+0 0 0  255 128 0  0 0 0
+";
+        assert_eq!(export(Format::Ppm), format!("P3\n{}{}", HEADER, body));
+    }
+
+    #[test]
+    fn bresenham() {
+        let points = |text: &str| {
+            let line: Line = text.parse().unwrap();
+            line.points().map(|p| (p.x, p.y)).collect::<Vec<_>>()
+        };
+        let shallow = vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 1)];
+        assert_eq!(points("0,0 -> 4,1"), shallow);
+        let shallow = vec![(4, 1), (3, 1), (2, 0), (1, 0), (0, 0)];
+        assert_eq!(points("4,1 -> 0,0"), shallow);
+        let steep = vec![(0, 0), (0, 1), (1, 2), (1, 3), (1, 4)];
+        assert_eq!(points("0,0 -> 1,4"), steep);
+        let steep = vec![(1, 4), (1, 3), (0, 2), (0, 1), (0, 0)];
+        assert_eq!(points("1,4 -> 0,0"), steep);
+        assert_eq!(points("2,3 -> 2,3"), vec![(2, 3)]);
+    }
+}
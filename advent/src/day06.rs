@@ -1,123 +1,271 @@
-use std::collections::VecDeque;
+use crate::matrix::{Arithmetic, Matrix};
 
 /// Simulate lanternfish for 80 days.
 pub fn part1(lines: &[String]) -> u64 {
-    simulate(80, lines)
+    simulate(80, lines).unwrap()
 }
 
-/// Simulate lanternfish for 256 days.
+/// Count lanternfish after 256 days.
 pub fn part2(lines: &[String]) -> u64 {
-    simulate(256, lines)
+    let school = School::parse(Model::LANTERNFISH, &lines[0]).unwrap();
+    school.population_after(256, Arithmetic::Checked).unwrap()
 }
 
-/// Simulate lanternfish for the specified number of days.
-///
-/// Group each fish by the number of days remaining before that fish
-/// produces a new fish. The cycle is 7 days long, but new fish start
-/// with a timer of 8, so we can use an array of size 9. For example,
-/// with this input:
-///
-/// ```plaintext
-/// 3,4,3,1,2
-/// ```
-///
-/// We can group each fish into the following buckets:
-/// ```plaintext
-/// 0: 0 // 0 fish with 0 days left
-/// 1: 1 // 1 fish with 1 day left
-/// 2: 1 // etc.
-/// 3: 2
-/// 4: 1
-/// 5: 0
-/// 6: 0
-/// 7: 0
-/// 8: 0
-/// ```
-///
-/// To add new fish to the end, we rotate our counters left, e.g. after 2 days:
-///
-/// ```plaintext
-///    0 1 2
-/// +-------> days
-/// |  0
-/// v  1 1
-/// 0: 1 1 1
-/// 1: 2 2 2
-/// 2: 1 1 1
-/// 3: 0 0 0
-/// 4: 0 0 0
-/// 5: 0 0 0
-/// 6: 0 0 0
-/// 7:   0 0
-/// 8:     1
-/// |
-/// +-------> fish
-///    5 5 …
-/// ```
-///
-/// Then, to put our original fish back into the cycle, _add_ the number
-/// of fish at the end (those just added) to index 6 (7 days left):
-/// ```plaintext
-///    0 1         2
-/// +---------------> days
-/// |  0
-/// v  1 1
-/// 0: 1 1 1       1
-/// 1: 2 2 2       1
-/// 2: 1 1 1       1
-/// 3: 0 0 0       0
-/// 4: 0 0 0       0
-/// 5: 0 0 0       0
-/// 6: 0 0 0 + 1 = 1
-/// 7:   0 0   |   0
-/// 8:     1 <-+   1
-/// |
-/// +---------------> fish
-///    5 5         6
-/// ```
-///
-/// After 2 more days:
-/// ```plaintext
-///    0 1         2         3         4
-/// +-----------------------------------> days
-/// |  0
-/// |  1 1
-/// |  1 1 1       1
-/// v  2 2 2       2 2       2
-/// 0: 1 1 1       1 1       1 1       1
-/// 1: 0 0 0       0 0       0 0       0
-/// 2: 0 0 0       0 0       0 0       0
-/// 3: 0 0 0       0 0       0 0       0
-/// 4: 0 0 0 + 1 = 1 1       1 1       1
-/// 5:   0 0   |   0 0 + 1 = 1 1       1
-/// 6:     1 <-+   1 1   |   1 1 + 2 = 3
-/// 7:               1 <-+   1 1   |   1
-/// 8:                         2 <-+   2
-/// |
-/// +-----------------------------------> fish
-///    5 5         6         7         9
-/// ```
-///
-/// After simulating for the specified number of days, return the sum
-/// of the elements of the array, which is the total number of fish in
-/// the system.
-pub fn simulate(days: usize, lines: &[String]) -> u64 {
-    let mut counters: VecDeque<u64> =
-        lines[0]
+/// Simulate lanternfish for the specified number of days, one day at a
+/// time. Returns `None` if the number of fish overflows a `u64`.
+pub fn simulate(days: usize, lines: &[String]) -> Option<u64> {
+    let mut school = School::parse(Model::LANTERNFISH, &lines[0])?;
+    for _ in 0..days {
+        school.step()?;
+    }
+    school.population()
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// How fish reproduce: a grown fish produces a new fish every `cycle`
+/// days, and a newborn takes `delay` extra days for its first cycle.
+pub struct Model {
+    cycle: usize,
+    delay: usize,
+}
+
+impl Model {
+    /// Lanternfish spawn every 7 days, and newborns take 2 more days
+    /// for their first cycle, so a school needs 9 counters. For
+    /// example, with this input:
+    ///
+    /// ```plaintext
+    /// 3,4,3,1,2
+    /// ```
+    ///
+    /// We can group each fish into the following buckets:
+    /// ```plaintext
+    /// 0: 0 // 0 fish with 0 days left
+    /// 1: 1 // 1 fish with 1 day left
+    /// 2: 1 // etc.
+    /// 3: 2
+    /// 4: 1
+    /// 5: 0
+    /// 6: 0
+    /// 7: 0
+    /// 8: 0
+    /// ```
+    ///
+    /// To add new fish to the end, we rotate our counters left, e.g. after 2 days:
+    ///
+    /// ```plaintext
+    ///    0 1 2
+    /// +-------> days
+    /// |  0
+    /// v  1 1
+    /// 0: 1 1 1
+    /// 1: 2 2 2
+    /// 2: 1 1 1
+    /// 3: 0 0 0
+    /// 4: 0 0 0
+    /// 5: 0 0 0
+    /// 6: 0 0 0
+    /// 7:   0 0
+    /// 8:     1
+    /// |
+    /// +-------> fish
+    ///    5 5 …
+    /// ```
+    ///
+    /// Then, to put our original fish back into the cycle, _add_ the number
+    /// of fish at the end (those just added) to index 6 (7 days left):
+    /// ```plaintext
+    ///    0 1         2
+    /// +---------------> days
+    /// |  0
+    /// v  1 1
+    /// 0: 1 1 1       1
+    /// 1: 2 2 2       1
+    /// 2: 1 1 1       1
+    /// 3: 0 0 0       0
+    /// 4: 0 0 0       0
+    /// 5: 0 0 0       0
+    /// 6: 0 0 0 + 1 = 1
+    /// 7:   0 0   |   0
+    /// 8:     1 <-+   1
+    /// |
+    /// +---------------> fish
+    ///    5 5         6
+    /// ```
+    ///
+    /// After 2 more days:
+    /// ```plaintext
+    ///    0 1         2         3         4
+    /// +-----------------------------------> days
+    /// |  0
+    /// |  1 1
+    /// |  1 1 1       1
+    /// v  2 2 2       2 2       2
+    /// 0: 1 1 1       1 1       1 1       1
+    /// 1: 0 0 0       0 0       0 0       0
+    /// 2: 0 0 0       0 0       0 0       0
+    /// 3: 0 0 0       0 0       0 0       0
+    /// 4: 0 0 0 + 1 = 1 1       1 1       1
+    /// 5:   0 0   |   0 0 + 1 = 1 1       1
+    /// 6:     1 <-+   1 1   |   1 1 + 2 = 3
+    /// 7:               1 <-+   1 1   |   1
+    /// 8:                         2 <-+   2
+    /// |
+    /// +-----------------------------------> fish
+    ///    5 5         6         7         9
+    /// ```
+    ///
+    /// The total number of fish in the system is the sum of the
+    /// counters.
+    pub const LANTERNFISH: Model = Model { cycle: 7, delay: 2 };
+
+    /// Create a model. Returns `None` if the cycle is empty.
+    pub fn new(cycle: usize, delay: usize) -> Option<Self> {
+        if cycle == 0 {
+            return None;
+        }
+        Some(Self { cycle, delay })
+    }
+
+    /// Days between each new fish a grown fish produces.
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    /// Extra days before a newborn fish starts its first cycle.
+    pub fn delay(&self) -> usize {
+        self.delay
+    }
+
+    /// Number of distinct timer values.
+    pub fn timers(&self) -> usize {
+        self.cycle + self.delay
+    }
+
+    /// Matrix mapping the count of fish with each timer value to the
+    /// counts one day later.
+    pub fn transition(&self) -> Matrix {
+        let n = self.timers();
+        let mut matrix = Matrix::zero(n);
+        for timer in 1..n {
+            matrix.set(timer - 1, timer, 1);
+        }
+        matrix.set(n - 1, 0, 1);
+        let reset = matrix.get(self.cycle - 1, 0);
+        matrix.set(self.cycle - 1, 0, reset + 1);
+        matrix
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Fish grouped by the number of days remaining before they produce a
+/// new fish.
+pub struct School {
+    model: Model,
+    counters: Vec<u64>,
+}
+
+impl School {
+    /// Create a school from each fish's timer. Returns `None` if any
+    /// timer is too long for the model.
+    pub fn new<I: IntoIterator<Item = usize>>(model: Model, timers: I) -> Option<Self> {
+        let mut counters = vec![0; model.timers()];
+        for timer in timers {
+            *counters.get_mut(timer)? += 1;
+        }
+        Some(Self { model, counters })
+    }
+
+    /// Parse a comma-separated list of timers.
+    pub fn parse(model: Model, line: &str) -> Option<Self> {
+        let timers = line
             .split(',')
-            .flat_map(str::parse)
-            .fold(VecDeque::from([0; 9]), |mut counters, i| {
-                counters[i] += 1;
-                counters
-            });
+            .map(|timer| timer.trim().parse().ok())
+            .collect::<Option<Vec<_>>>()?;
+        Self::new(model, timers)
+    }
 
-    for _ in 0..days {
-        counters.rotate_left(1);
-        counters[6] += counters[8];
+    /// Number of fish with each timer value.
+    pub fn counters(&self) -> &[u64] {
+        &self.counters
+    }
+
+    /// Total number of fish. Returns `None` on overflow.
+    pub fn population(&self) -> Option<u64> {
+        self.counters
+            .iter()
+            .try_fold(0u64, |total, &count| total.checked_add(count))
+    }
+
+    /// Advance the school by a day. Every timer counts down by one, and
+    /// each fish whose timer was at zero restarts its cycle with a timer
+    /// of `cycle - 1` and produces a new fish with a timer of
+    /// `cycle + delay - 1`. Returns `None` on overflow.
+    pub fn step(&mut self) -> Option<()> {
+        let reset = self.model.cycle - 1;
+        let born = self.counters[0];
+        self.counters.rotate_left(1);
+        self.counters[reset] = self.counters[reset].checked_add(born)?;
+        Some(())
     }
 
-    counters.into_iter().sum()
+    /// Number of fish after the specified number of days, computed by
+    /// raising the model's transition matrix to that power. Returns
+    /// `None` on overflow.
+    pub fn population_after(&self, days: u64, arithmetic: Arithmetic) -> Option<u64> {
+        let matrix = self.model.transition().pow(days, arithmetic)?;
+        let counters = matrix.apply(&self.counters, arithmetic)?;
+        counters
+            .into_iter()
+            .try_fold(0, |total, count| arithmetic.add(total, count))
+    }
 }
 
 check!(ex 1 = 5934, ex 2 = 26984457539, part 1 = 358214, part 2 = 1622533344325);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn empty_cycle() {
+        assert_eq!(Model::new(0, 2), None);
+        assert_eq!(Model::new(7, 2), Some(Model::LANTERNFISH));
+    }
+
+    #[test]
+    fn step_matches_matrix() {
+        let model = Model::new(3, 1).unwrap();
+        let start = School::new(model, vec![0, 1, 3, 3]).unwrap();
+        let mut school = start.clone();
+        for days in 0..50 {
+            let expected = start.population_after(days, Arithmetic::Checked);
+            assert_eq!(school.population(), expected);
+            school.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn overflow() {
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        assert_eq!(simulate(256, &lines), Some(26984457539));
+        assert_eq!(simulate(1000, &lines), None);
+        let school = School::parse(Model::LANTERNFISH, &lines[0]).unwrap();
+        assert_eq!(school.population_after(1000, Arithmetic::Checked), None);
+    }
+
+    #[test]
+    fn population_overflow() {
+        // Every step still fits, even on the first day the total doesn't.
+        let lines: Vec<String> = crate::check::parse_lines(tests::EXAMPLE);
+        let mut school = School::parse(Model::LANTERNFISH, &lines[0]).unwrap();
+        let mut days = 0;
+        while school.population().is_some() {
+            school.step().unwrap();
+            days += 1;
+        }
+        assert_eq!(simulate(days, &lines), None);
+        assert!(simulate(days - 1, &lines).is_some());
+    }
+}
@@ -2,29 +2,125 @@ use itertools::Itertools;
 
 /// Find minimum fuel cost when each step uses 1 unit of fuel.
 pub fn part1(lines: &[String]) -> i64 {
-    min_fuel_cost(lines, |i, j| (j - i).abs())
+    align_linear(&parse(lines)).unwrap().fuel
 }
 
 /// Find minimum fuel cost when each step uses 1 unit of fuel more than the previous step.
 pub fn part2(lines: &[String]) -> i64 {
-    min_fuel_cost(lines, |i, j| {
-        let n = (j - i).abs();
-        n * (n + 1) / 2
+    align_triangular(&parse(lines)).unwrap().fuel
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Position every crab moves to, and the fuel it takes to get there.
+pub struct Alignment {
+    /// Where the crabs line up.
+    pub position: i64,
+    /// Total fuel used by every crab.
+    pub fuel: i64,
+}
+
+/// Total fuel used moving every crab to the position.
+pub fn fuel<F>(positions: &[i64], position: i64, cost: F) -> i64
+where
+    F: Fn(i64, i64) -> i64,
+{
+    positions.iter().map(|&crab| cost(position, crab)).sum()
+}
+
+/// Fuel used by a crab when each step uses 1 unit of fuel.
+pub fn linear(i: i64, j: i64) -> i64 {
+    (j - i).abs()
+}
+
+/// Fuel used by a crab when each step uses 1 unit of fuel more than the
+/// previous step.
+pub fn triangular(i: i64, j: i64) -> i64 {
+    let n = (j - i).abs();
+    n * (n + 1) / 2
+}
+
+/// Align the crabs with linear cost. The sum of distances is smallest
+/// at the median.
+pub fn align_linear(positions: &[i64]) -> Option<Alignment> {
+    let sorted = positions.iter().copied().sorted().collect_vec();
+    let position = *sorted.get(sorted.len().checked_sub(1)? / 2)?;
+    Some(Alignment {
+        position,
+        fuel: fuel(positions, position, linear),
     })
 }
 
-/// Find minimum fuel cost by applying cost function to each position and possible distance.
-pub fn min_fuel_cost<F>(lines: &[String], cost: F) -> i64
+/// Align the crabs with triangular cost. The total is a sum of squares
+/// plus a sum of distances, so the best position is within a step of
+/// the mean.
+pub fn align_triangular(positions: &[i64]) -> Option<Alignment> {
+    if positions.is_empty() {
+        return None;
+    }
+    let mean = positions
+        .iter()
+        .sum::<i64>()
+        .div_euclid(positions.len() as i64);
+    (mean - 1..=mean + 1)
+        .map(|position| Alignment {
+            position,
+            fuel: fuel(positions, position, triangular),
+        })
+        .min_by_key(|alignment| alignment.fuel)
+}
+
+/// Align the crabs with any cost function whose total is convex in the
+/// position, using ternary search between the outermost crabs.
+pub fn align<F>(positions: &[i64], cost: F) -> Option<Alignment>
 where
     F: Fn(i64, i64) -> i64,
 {
-    let positions: Vec<i64> = lines[0].split(',').flat_map(str::parse).collect();
-    let (&min, &max) = positions.iter().minmax().into_option().unwrap();
-    (min..=max)
-        .map(|i| positions.iter().map(|&j| cost(i, j)).sum())
-        .min()
-        .unwrap()
+    let (&min, &max) = positions.iter().minmax().into_option()?;
+    let total = |position| fuel(positions, position, &cost);
+
+    let (mut lo, mut hi) = (min, max);
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        if total(lo + third) <= total(hi - third) {
+            hi -= third;
+        } else {
+            lo += third;
+        }
+    }
+
+    (lo..=hi)
+        .map(|position| Alignment {
+            position,
+            fuel: total(position),
+        })
+        .min_by_key(|alignment| alignment.fuel)
+}
+
+fn parse(lines: &[String]) -> Vec<i64> {
+    lines[0].split(',').flat_map(str::parse).collect()
 }
 
 check!(ex 1 = 37, ex 2 = 168, part 1 = 335330, part 2 = 92439766);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn ternary_search() {
+        let example = parse(&crate::check::parse_lines(tests::EXAMPLE));
+        let at = |position, fuel| Some(Alignment { position, fuel });
+        assert_eq!(align(&example, linear).map(|a| a.fuel), Some(37));
+        assert_eq!(align(&example, triangular), at(5, 168));
+        assert_eq!(align(&example, |i, j| (j - i) * (j - i)), at(5, 291));
+
+        let input = parse(&crate::check::parse_lines(tests::INPUT));
+        let fuel = |alignment: Option<Alignment>| alignment.map(|a| a.fuel);
+        assert_eq!(fuel(align(&input, linear)), fuel(align_linear(&input)));
+        assert_eq!(align(&input, triangular), align_triangular(&input));
+
+        assert_eq!(align(&[], linear), None);
+        assert_eq!(align(&[4], triangular), at(4, 0));
+    }
+}
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
 use std::ops::{BitAnd, BitOr, Index, IndexMut};
 use std::str::FromStr;
 
@@ -13,71 +13,288 @@ pub fn part1(entries: &[Entry]) -> usize {
 
 /// Deduce each display's outputs and sum the results.
 pub fn part2(entries: &[Entry]) -> u64 {
-    entries.iter().map(solve_entry).sum()
+    let glyphs = Glyphs::digits();
+    entries
+        .iter()
+        .map(|entry| solve_entry(&glyphs, entry).unwrap().value(10).unwrap())
+        .sum()
 }
 
-/// Deduce a single display's output.
-pub fn solve_entry(entry: &Entry) -> u64 {
-    let mut digits = [Pattern::default(); 10];
-
-    // Find simple cases first
-    entry
-        .patterns
+/// Deduce a single display's wiring and decode its outputs.
+pub fn solve_entry(glyphs: &Glyphs, entry: &Entry) -> Result<Decoded, DecodeError> {
+    let wiring = glyphs.wiring(&entry.patterns)?;
+    let symbols = entry
+        .outputs
         .iter()
-        .for_each(|&pattern| match pattern.segments() {
-            2 => digits[1] = pattern,
-            3 => digits[7] = pattern,
-            4 => digits[4] = pattern,
-            7 => digits[8] = pattern,
-            _ => {}
-        });
-
-    // Deduce remaining cases
-    entry
-        .patterns
-        .iter()
-        .for_each(|&pattern| match pattern.segments() {
-            5 if (pattern & digits[1]) == digits[1] => digits[3] = pattern,
-            5 if (pattern | digits[4]) == digits[8] => digits[2] = pattern,
-            5 => digits[5] = pattern,
-            6 if (pattern & digits[4]) == digits[4] => digits[9] = pattern,
-            6 if (pattern & digits[1]) == digits[1] => digits[0] = pattern,
-            6 => digits[6] = pattern,
-            _ => {}
-        });
-
-    // Match output to known patterns
-    entry.outputs.iter().fold(0, |acc, &pattern| {
-        // Linear search over 10-element array is faster than using
-        // a hashmap or `[u8; 128]`
-        let digit = digits.iter().position(|&p| p == pattern).unwrap();
-        acc * 10 + digit as u64
-    })
-}
-
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-/// A single entry in the input representing a 7-segment display.
+        .map(|&output| {
+            let pattern = wiring.apply(output).ok_or(DecodeError::Unwired(output))?;
+            glyphs.symbol(pattern).ok_or(DecodeError::Unknown(output))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Decoded { wiring, symbols })
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A display's wiring along with the symbols shown on its outputs.
+pub struct Decoded {
+    /// Wire-to-segment permutation.
+    pub wiring: Wiring,
+    /// Symbol shown by each output.
+    pub symbols: Vec<char>,
+}
+
+impl Decoded {
+    /// Read the symbols as a number in the radix, if they're all digits.
+    pub fn value(&self, radix: u32) -> Option<u64> {
+        self.symbols.iter().try_fold(0, |acc, c| {
+            Some(acc * radix as u64 + c.to_digit(radix)? as u64)
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Why an entry couldn't be decoded.
+pub enum DecodeError {
+    /// No wiring maps every pattern onto a glyph.
+    Inconsistent,
+    /// More than one wiring maps every pattern onto a glyph.
+    Ambiguous(Vec<Wiring>),
+    /// The wiring doesn't map this output onto a glyph.
+    Unknown(Pattern),
+    /// This pattern uses a wire past the display's last segment.
+    Unwired(Pattern),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            DecodeError::Inconsistent => write!(f, "no wiring matches the patterns"),
+            DecodeError::Ambiguous(wirings) => {
+                write!(f, "wirings ")?;
+                for (i, wiring) in wirings.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " and ")?;
+                    }
+                    write!(f, "{}", wiring)?;
+                }
+                write!(f, " both match the patterns")
+            }
+            DecodeError::Unknown(pattern) => write!(f, "output {} isn't a glyph", pattern),
+            DecodeError::Unwired(pattern) => {
+                write!(f, "pattern {} uses a wire past the last segment", pattern)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Permutation mapping each wire to the segment it lights.
+pub struct Wiring {
+    segments: Vec<usize>,
+}
+
+impl Wiring {
+    /// Segment lit by each wire.
+    pub fn segments(&self) -> &[usize] {
+        &self.segments
+    }
+
+    /// Map a pattern of wires to the pattern of segments they light.
+    /// Returns `None` if the pattern uses a wire the wiring doesn't
+    /// cover.
+    pub fn apply(&self, wires: Pattern) -> Option<Pattern> {
+        if !wires.fits(self.segments.len()) {
+            return None;
+        }
+        let bits = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|&(wire, _)| wires.bits >> wire & 1 == 1)
+            .fold(0, |bits, (_, &segment)| bits | 1 << segment);
+        Some(Pattern::new(bits))
+    }
+}
+
+impl Display for Wiring {
+    /// Written as the segment for wire `a`, then for wire `b`, etc.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        for &segment in &self.segments {
+            write!(f, "{}", (b'a' + segment as u8) as char)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Table of symbols a display can show and the segments lit for each.
+pub struct Glyphs {
+    segments: usize,
+    glyphs: Vec<(char, Pattern)>,
+}
+
+impl Glyphs {
+    /// Create a glyph table for a display with up to 16 segments.
+    /// Returns `None` if any glyph lights a segment past the last one
+    /// or two glyphs share a pattern.
+    pub fn new(segments: usize, glyphs: &[(char, &str)]) -> Option<Self> {
+        if segments > 16 {
+            return None;
+        }
+        let mut table: Vec<(char, Pattern)> = vec![];
+        for &(symbol, lit) in glyphs {
+            let pattern = lit.parse::<Pattern>().ok()?;
+            if !pattern.fits(segments) || table.iter().any(|&(_, p)| p == pattern) {
+                return None;
+            }
+            table.push((symbol, pattern));
+        }
+        Some(Self {
+            segments,
+            glyphs: table,
+        })
+    }
+
+    /// Standard seven-segment digits 0–9.
+    pub fn digits() -> Self {
+        Self::new(
+            7,
+            &[
+                ('0', "abcefg"),
+                ('1', "cf"),
+                ('2', "acdeg"),
+                ('3', "acdfg"),
+                ('4', "bcdf"),
+                ('5', "abdfg"),
+                ('6', "abdefg"),
+                ('7', "acf"),
+                ('8', "abcdefg"),
+                ('9', "abcdfg"),
+            ],
+        )
+        .unwrap()
+    }
+
+    /// Symbol drawn with exactly these segments.
+    pub fn symbol(&self, pattern: Pattern) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|&&(_, p)| p == pattern)
+            .map(|&(symbol, _)| symbol)
+    }
+
+    /// Find the wiring that maps every pattern onto a glyph.
+    ///
+    /// A wire can only light a segment if the patterns using that wire
+    /// have the same lengths as the glyphs using that segment. Wires
+    /// are then assigned one at a time, backtracking as soon as a
+    /// pattern whose wires are all assigned doesn't map onto a glyph.
+    pub fn wiring(&self, patterns: &[Pattern]) -> Result<Wiring, DecodeError> {
+        let n = self.segments;
+        if let Some(&pattern) = patterns.iter().find(|pattern| !pattern.fits(n)) {
+            return Err(DecodeError::Unwired(pattern));
+        }
+        let signature = |patterns: &mut dyn Iterator<Item = Pattern>, bit: usize| {
+            let mut lengths = patterns
+                .filter(|pattern| pattern.bits >> bit & 1 == 1)
+                .map(|pattern| pattern.segments())
+                .collect::<Vec<_>>();
+            lengths.sort_unstable();
+            lengths
+        };
+
+        // Only constrain by length when every glyph is shown.
+        let complete = patterns.len() == self.glyphs.len();
+        let candidates = (0..n)
+            .map(|wire| {
+                let wires = signature(&mut patterns.iter().copied(), wire);
+                (0..n)
+                    .filter(|&segment| {
+                        let glyphs = &mut self.glyphs.iter().map(|&(_, p)| p);
+                        !complete || signature(glyphs, segment) == wires
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut found = vec![];
+        let mut segments = vec![usize::MAX; n];
+        self.assign(patterns, &candidates, 0, &mut segments, &mut found);
+        match found.len() {
+            0 => Err(DecodeError::Inconsistent),
+            1 => Ok(found.remove(0)),
+            _ => Err(DecodeError::Ambiguous(found)),
+        }
+    }
+
+    /// Try each candidate segment for the wire, stopping once two
+    /// wirings have been found.
+    fn assign(
+        &self,
+        patterns: &[Pattern],
+        candidates: &[Vec<usize>],
+        wire: usize,
+        segments: &mut Vec<usize>,
+        found: &mut Vec<Wiring>,
+    ) {
+        if wire == candidates.len() {
+            found.push(Wiring {
+                segments: segments.clone(),
+            });
+            return;
+        }
+
+        let assigned = Pattern::new(((1u32 << (wire + 1)) - 1) as u16);
+        for &segment in &candidates[wire] {
+            if found.len() > 1 || segments[..wire].contains(&segment) {
+                continue;
+            }
+            segments[wire] = segment;
+            let wiring = Wiring {
+                segments: segments[..=wire].to_vec(),
+            };
+            let consistent = patterns
+                .iter()
+                .filter(|&&pattern| pattern & assigned == pattern)
+                .all(|&pattern| wiring.apply(pattern).and_then(|p| self.symbol(p)).is_some());
+            if consistent {
+                self.assign(patterns, candidates, wire + 1, segments, found);
+            }
+        }
+        segments[wire] = usize::MAX;
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A single entry in the input representing a segmented display.
 pub struct Entry {
-    patterns: [Pattern; 10],
-    outputs: [Pattern; 4],
+    patterns: Vec<Pattern>,
+    outputs: Vec<Pattern>,
 }
 
 impl FromStr for Entry {
-    type Err = !;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (lhs, rhs) = s.split_once(" | ").unwrap();
-        let lhs: Vec<Pattern> = lhs.split_whitespace().flat_map(str::parse).collect();
-        let rhs: Vec<Pattern> = rhs.split_whitespace().flat_map(str::parse).collect();
+        let (lhs, rhs) = s
+            .split_once(" | ")
+            .ok_or_else(|| format!("missing separator in {:?}", s))?;
         Ok(Self {
-            patterns: lhs.try_into().unwrap(),
-            outputs: rhs.try_into().unwrap(),
+            patterns: lhs
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+            outputs: rhs
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
-/// A segment pattern representing some digit.
+/// A segment pattern representing some glyph, with up to 16 segments
+/// named `a` through `p`.
 ///
 /// The pattern is a bitfield where a 1 bit represents a segment used
 /// in the digit. For example, the number 4 could be represented by
@@ -99,14 +316,20 @@ impl FromStr for Entry {
 /// 0b0101110
 /// ```
 pub struct Pattern {
-    byte: u8,
+    bits: u16,
 }
 
 impl Pattern {
     #[inline]
-    /// Create a new pattern from a byte.
-    pub fn new(byte: u8) -> Self {
-        Self { byte }
+    /// Create a new pattern from its bits.
+    pub fn new(bits: u16) -> Self {
+        Self { bits }
+    }
+
+    #[inline]
+    /// Does the pattern only use the first `segments` segments?
+    pub fn fits(&self, segments: usize) -> bool {
+        self.bits.checked_shr(segments as u32).unwrap_or(0) == 0
     }
 
     #[inline]
@@ -114,7 +337,7 @@ impl Pattern {
     ///
     /// This is actually its population count.
     pub fn segments(&self) -> u8 {
-        self.byte.count_ones() as u8
+        self.bits.count_ones() as u8
     }
 }
 
@@ -130,7 +353,7 @@ impl BitAnd for Pattern {
 
     #[inline]
     fn bitand(self, rhs: Self) -> Self::Output {
-        Pattern::new(self.byte & rhs.byte)
+        Pattern::new(self.bits & rhs.bits)
     }
 }
 
@@ -139,7 +362,7 @@ impl BitOr for Pattern {
 
     #[inline]
     fn bitor(self, rhs: Self) -> Self::Output {
-        Pattern::new(self.byte | rhs.byte)
+        Pattern::new(self.bits | rhs.bits)
     }
 }
 
@@ -148,29 +371,121 @@ impl<T> Index<Pattern> for [T] {
 
     #[inline]
     fn index(&self, pattern: Pattern) -> &Self::Output {
-        self.index(pattern.byte as usize)
+        self.index(pattern.bits as usize)
     }
 }
 
 impl<T> IndexMut<Pattern> for [T] {
     #[inline]
     fn index_mut(&mut self, pattern: Pattern) -> &mut Self::Output {
-        self.index_mut(pattern.byte as usize)
+        self.index_mut(pattern.bits as usize)
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        for bit in 0..16 {
+            if self.bits >> bit & 1 == 1 {
+                write!(f, "{}", (b'a' + bit) as char)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl FromStr for Pattern {
-    type Err = !;
+    type Err = String;
 
+    /// Parse the letters of the lit segments, from `a` to `p`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut byte = 0;
-        for c in s.bytes() {
-            assert!(b"abcdefg".contains(&c));
-            byte |= 1 << (c - b'a');
+        let mut bits = 0;
+        for c in s.chars() {
+            if !('a'..='p').contains(&c) {
+                return Err(format!("invalid segment {:?}", c));
+            }
+            bits |= 1 << (c as u8 - b'a');
         }
-        Ok(Pattern::new(byte))
+        Ok(Pattern::new(bits))
     }
 }
 
 check!(ex 1 = 26, ex 2 = 61229, part 1 = 342, part 2 = 1068933);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn invalid_segments() {
+        assert_eq!("cf".parse(), Ok(Pattern::new(0b100100)));
+        assert!("cF".parse::<Pattern>().is_err());
+        assert!("aq".parse::<Pattern>().is_err());
+        assert_eq!(Glyphs::new(7, &[('1', "cF")]), None);
+        assert_eq!(Glyphs::new(7, &[('1', "cf"), ('7', "ah")]), None);
+    }
+
+    #[test]
+    fn four_segments() {
+        let glyphs =
+            Glyphs::new(4, &[('a', "a"), ('b', "ab"), ('c', "abc"), ('d', "abcd")]).unwrap();
+        let entry = "c ca cad cadb | cadb c ca".parse().unwrap();
+        let decoded = solve_entry(&glyphs, &entry).unwrap();
+        assert_eq!(decoded.wiring.to_string(), "bdac");
+        assert_eq!(decoded.symbols, vec!['d', 'a', 'b']);
+        assert_eq!(decoded.value(16), Some(0xdab));
+    }
+
+    #[test]
+    fn ambiguous() {
+        let glyphs = Glyphs::new(2, &[('x', "a"), ('y', "b")]).unwrap();
+        let patterns = ["a".parse().unwrap(), "b".parse().unwrap()];
+        match glyphs.wiring(&patterns) {
+            Err(DecodeError::Ambiguous(wirings)) => {
+                let wirings = wirings.iter().map(Wiring::to_string).collect::<Vec<_>>();
+                assert_eq!(wirings, ["ab", "ba"]);
+            }
+            other => panic!("expected an ambiguous wiring, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn inconsistent() {
+        let glyphs = Glyphs::new(2, &[('x', "a"), ('y', "ab")]).unwrap();
+        let patterns = ["ab".parse().unwrap(), "ab".parse().unwrap()];
+        assert_eq!(glyphs.wiring(&patterns), Err(DecodeError::Inconsistent));
+
+        let entry = "ab a | b".parse().unwrap();
+        let unknown = Pattern::new(0b10);
+        assert_eq!(
+            solve_entry(&glyphs, &entry),
+            Err(DecodeError::Unknown(unknown))
+        );
+    }
+
+    #[test]
+    fn unwired() {
+        let glyphs = Glyphs::new(2, &[('x', "a"), ('y', "ab")]).unwrap();
+        let wiring = glyphs.wiring(&["a".parse().unwrap(), "ab".parse().unwrap()]);
+        let wiring = wiring.unwrap();
+        assert_eq!(wiring.apply(Pattern::new(0b10)), Some(Pattern::new(0b10)));
+        assert_eq!(wiring.apply(Pattern::new(0b100)), None);
+
+        let stray = Pattern::new(0b101);
+        let patterns = [Pattern::new(0b1), stray];
+        assert_eq!(glyphs.wiring(&patterns), Err(DecodeError::Unwired(stray)));
+        let entry = "a ab | ac".parse().unwrap();
+        assert_eq!(
+            solve_entry(&glyphs, &entry),
+            Err(DecodeError::Unwired(stray))
+        );
+    }
+
+    #[test]
+    fn bad_entries() {
+        assert!("ab cd | ef".parse::<Entry>().is_ok());
+        assert!("ab cd ef".parse::<Entry>().is_err());
+        assert!("ab cD | ef".parse::<Entry>().is_err());
+        assert!("ab cd | ez".parse::<Entry>().is_err());
+    }
+}
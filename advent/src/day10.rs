@@ -1,3 +1,4 @@
+use crate::brackets::{Brackets, Diagnostic, Score};
 use itertools::Itertools;
 
 /// Score corrupted lines.
@@ -6,34 +7,10 @@ use itertools::Itertools;
 /// These can be identified by maintaining a stack of expected brackets
 /// and comparing as they're popped off. Incomplete lines are ignored.
 pub fn part1(lines: &[String]) -> u64 {
-    fn score(c: char) -> u64 {
-        match c {
-            ')' => 3,
-            ']' => 57,
-            '}' => 1197,
-            '>' => 25137,
-            _ => panic!("Unexpected character {:?}", c),
-        }
-    }
-
+    let brackets = Brackets::standard();
     lines
         .iter()
-        .map(|line| {
-            let mut stack = Vec::new();
-            for c in line.chars() {
-                if "([{<".contains(c) {
-                    stack.push(close(c));
-                    continue;
-                }
-
-                if let Some(expected) = stack.pop() {
-                    if c != expected {
-                        return score(c);
-                    }
-                }
-            }
-            0
-        })
+        .filter_map(|line| SyntaxError.score(&brackets.check(line)))
         .sum()
 }
 
@@ -43,48 +20,54 @@ pub fn part1(lines: &[String]) -> u64 {
 /// brackets. These can be reconstructed by maintaining a stack of
 /// expected brackets. Corrupted lines are ignored.
 pub fn part2(lines: &[String]) -> u64 {
-    fn score(c: char) -> u64 {
-        match c {
-            ')' => 1,
-            ']' => 2,
-            '}' => 3,
-            '>' => 4,
-            _ => panic!("Unexpected character {:?}", c),
-        }
-    }
-
+    let brackets = Brackets::standard();
     let scores: Vec<u64> = lines
         .iter()
-        .filter_map(|line| {
-            let mut stack = Vec::new();
-            for c in line.chars() {
-                if "([{<".contains(c) {
-                    stack.push(close(c));
-                    continue;
-                }
-
-                if let Some(expected) = stack.pop() {
-                    if c != expected {
-                        return None;
-                    }
-                }
-            }
-
-            Some(stack.iter().rev().fold(0, |acc, &c| acc * 5 + score(c)))
-        })
+        .filter_map(|line| Autocomplete.score(&brackets.check(line)))
         .sorted()
         .collect();
 
     scores[scores.len() / 2]
 }
 
-fn close(c: char) -> char {
-    match c {
-        '(' => ')',
-        '[' => ']',
-        '{' => '}',
-        '<' => '>',
-        _ => panic!("Unexpected character {:?}", c),
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Scores the first illegal character on corrupted lines.
+pub struct SyntaxError;
+
+impl Score for SyntaxError {
+    fn score(&self, diagnostic: &Diagnostic) -> Option<u64> {
+        match diagnostic {
+            Diagnostic::Corrupted { found, .. } => match found {
+                ')' => Some(3),
+                ']' => Some(57),
+                '}' => Some(1197),
+                '>' => Some(25137),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Scores the completion string for incomplete lines.
+pub struct Autocomplete;
+
+impl Score for Autocomplete {
+    fn score(&self, diagnostic: &Diagnostic) -> Option<u64> {
+        match diagnostic {
+            Diagnostic::Incomplete { completion } => completion.chars().try_fold(0, |acc, c| {
+                let points = match c {
+                    ')' => 1,
+                    ']' => 2,
+                    '}' => 3,
+                    '>' => 4,
+                    _ => return None,
+                };
+                Some(acc * 5 + points)
+            }),
+            _ => None,
+        }
     }
 }
 
@@ -1,6 +1,5 @@
-use itertools::Itertools;
-use std::collections::HashMap;
-use std::collections::VecDeque;
+use crate::grid::{Grid, Pos};
+use std::collections::{HashSet, VecDeque};
 
 /// Evolve space for 100 steps and count the number of flashes
 pub fn part1(lines: &[String]) -> usize {
@@ -10,82 +9,96 @@ pub fn part1(lines: &[String]) -> usize {
 
 /// Find the first step where every octopus flashes together
 pub fn part2(lines: &[String]) -> usize {
-    let mut space = Space::parse(lines);
-    (1..)
-        .map(|i| (i, space.step()))
-        .skip_while(|&(_, x)| x < 100)
-        .map(|(i, _)| i)
-        .next()
-        .unwrap()
+    Space::parse(lines).synchronize().unwrap()
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+/// Energy levels of a rectangular grid of octopuses.
 struct Space {
-    space: HashMap<(usize, usize), u8>,
+    space: Grid<u8>,
 }
 
 impl Space {
-    fn new(space: HashMap<(usize, usize), u8>) -> Self {
+    fn new(space: Grid<u8>) -> Self {
         Self { space }
     }
 
     fn parse(lines: &[String]) -> Self {
-        let space = lines
-            .iter()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.bytes()
-                    .enumerate()
-                    .map(|(j, byte)| ((i, j), byte - b'0'))
-                    .collect_vec()
-            })
-            .collect();
-
-        Self::new(space)
+        Self::new(Grid::digits(lines).unwrap())
+    }
+
+    /// Number of columns.
+    fn width(&self) -> usize {
+        self.space.width()
+    }
+
+    /// Number of rows.
+    fn height(&self) -> usize {
+        self.space.height()
+    }
+
+    /// Step until every octopus flashes at once, returning that step.
+    /// Since each step is deterministic, seeing the same energy levels
+    /// twice means the octopuses are in a cycle that never syncs up.
+    fn synchronize(&mut self) -> Option<usize> {
+        let total = self.width() * self.height();
+        let mut seen = HashSet::new();
+        for i in 1.. {
+            if !seen.insert(self.space.clone()) {
+                return None;
+            }
+            if self.step() == total {
+                return Some(i);
+            }
+        }
+        None
     }
 
     fn step(&mut self) -> usize {
-        let mut queue: VecDeque<(usize, usize)> = (0..10).cartesian_product(0..10).collect();
-        let mut flashed: Vec<(usize, usize)> = Vec::new();
+        let mut queue: VecDeque<Pos> = self.space.positions().collect();
+        let mut flashed: Vec<Pos> = Vec::new();
 
         while let Some(point) = queue.pop_front() {
-            if self.space[&point] > 9 {
+            if self.space[point] > 9 {
                 continue;
             }
 
-            self.space.entry(point).and_modify(|level| *level += 1);
+            self.space[point] += 1;
 
-            if self.space[&point] > 9 {
+            if self.space[point] > 9 {
                 flashed.push(point);
-                for neighbor in neighbors(point) {
-                    queue.push_back(neighbor)
-                }
+                queue.extend(self.space.neighbors8(point));
             }
         }
 
         let flashes = flashed.len();
         for point in flashed {
-            self.space.insert(point, 0);
+            self.space[point] = 0;
         }
         flashes
     }
 }
 
-fn neighbors(point: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
-    let range = 0..10;
-    let row = point.0 as isize;
-    let col = point.1 as isize;
-    (-1..=1)
-        .cartesian_product(-1..=1)
-        .filter(|&(dy, dx)| dy != 0 || dx != 0)
-        .filter_map(move |(dy, dx)| {
-            let point = (row + dy, col + dx);
-            if range.contains(&point.0) && range.contains(&point.1) {
-                return Some((point.0 as usize, point.1 as usize));
-            }
-            None
-        })
-}
-
 check!(ex 1 = 1656, ex 2 = 195, part 1 = 1613, part 2 = 510);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    fn synchronize(text: &str) -> Option<usize> {
+        Space::parse(&crate::check::parse_lines(text)).synchronize()
+    }
+
+    #[test]
+    fn non_square() {
+        assert_eq!(synchronize("543\n128"), Some(31));
+        assert_eq!(synchronize("12345\n67899"), Some(72));
+    }
+
+    #[test]
+    fn never_syncs() {
+        assert_eq!(synchronize("002"), None);
+        assert_eq!(synchronize("12345\n67890"), None);
+    }
+}
@@ -1,96 +1,343 @@
+use std::collections::HashMap;
+
 /// Count paths through caves. Small caves can only be visited once.
 pub fn part1(lines: &[String]) -> usize {
     let graph = parse_graph(lines);
-    count_paths(&graph, Mode::Once)
+    count_paths(&graph, &Rules::once())
 }
 
 /// Count paths through caves. One small cave may be visited twice; all
 /// other small caves can only be visited once.
 pub fn part2(lines: &[String]) -> usize {
     let graph = parse_graph(lines);
-    count_paths(&graph, Mode::Twice)
+    count_paths(&graph, &Rules::twice(1))
+}
+
+/// Count paths through graph from `start` to `end` following the rules.
+/// Counts are memoised when every cave is either unlimited or limited
+/// to a single visit, and there are at most 128 limited caves.
+/// Otherwise each path is walked.
+pub fn count_paths(graph: &Graph<'_>, rules: &Rules) -> usize {
+    if let Some(mut counter) = Counter::new(graph, rules) {
+        return counter.count();
+    }
+
+    let mut paths = Paths::new(graph, rules);
+    let mut count = 0;
+    while paths.advance() {
+        count += 1;
+    }
+    count
+}
+
+/// Path from `start` to `end` with the most caves, if there is one.
+pub fn longest_path<'a>(graph: &Graph<'a>, rules: &Rules) -> Option<Vec<&'a str>> {
+    Paths::new(graph, rules).max_by_key(Vec::len)
+}
+
+/// Path from `start` to `end` with the fewest caves, if there is one.
+pub fn shortest_path<'a>(graph: &Graph<'a>, rules: &Rules) -> Option<Vec<&'a str>> {
+    Paths::new(graph, rules).min_by_key(Vec::len)
 }
 
-/// Count paths through graph from `start` to `end`. Final argument indicates
-/// whether a single small cave can be visited twice or not.
-pub fn count_paths<'a>(graph: &'a Graph<'a>, mode: Mode) -> usize {
-    let mut path = Path::new();
-    let twice = match mode {
-        Mode::Once => true,
-        Mode::Twice => false,
-    };
-    walk(graph, &mut path, twice, graph.start)
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// How many times each cave may be visited along a path. By default
+/// small caves can be visited once and big caves any number of times.
+/// `start` is never revisited, and a path stops as soon as it reaches
+/// `end`.
+pub struct Rules {
+    limits: Vec<(String, usize)>,
+    twice: usize,
 }
 
-fn walk<'a>(graph: &'a Graph<'a>, path: &mut Path, mut twice: bool, node: usize) -> usize {
-    let kind = graph.kind(node);
-    if kind == Kind::End {
-        return 1;
+impl Rules {
+    /// Each small cave can be visited once.
+    pub fn once() -> Self {
+        Self::twice(0)
     }
 
-    if kind == Kind::Small && path.occurrences(node) > 0 {
-        if twice {
-            return 0;
+    /// Up to `n` small caves can be visited twice.
+    pub fn twice(n: usize) -> Self {
+        Self {
+            limits: vec![],
+            twice: n,
         }
-        twice = true;
     }
 
-    path.push(node);
+    /// Limit the number of visits to a cave, small or big. Caves with
+    /// a limit are never visited an extra time. Limits on `start` and
+    /// `end` have no effect, since `start` is always visited once and
+    /// every path ends the first time it reaches `end`.
+    pub fn limit(mut self, cave: &str, visits: usize) -> Self {
+        self.limits.retain(|(name, _)| name != cave);
+        self.limits.push((cave.to_string(), visits));
+        self
+    }
 
-    let paths = graph
-        .neighbors(node)
-        .filter(|&&neighbor| graph.kind(neighbor) != Kind::Start)
-        .map(|&neighbor| walk(graph, path, twice, neighbor))
-        .sum();
+    /// Number of visits allowed to each vertex of the graph, and
+    /// whether it may be visited one extra time.
+    fn resolve(&self, graph: &Graph<'_>) -> Vec<(usize, bool)> {
+        (0..graph.len())
+            .map(|i| {
+                let limit = self
+                    .limits
+                    .iter()
+                    .find(|(name, _)| name == graph.verts[i])
+                    .map(|&(_, visits)| visits);
+                match (graph.kind(i), limit) {
+                    (Kind::Start, _) => (1, false),
+                    (_, Some(visits)) => (visits, false),
+                    (Kind::Small, None) => (1, true),
+                    (_, None) => (usize::MAX, false),
+                }
+            })
+            .collect()
+    }
+}
 
-    path.pop();
-    paths
+#[derive(Debug)]
+/// Iterator over every path from `start` to `end` allowed by the
+/// rules, each as a sequence of cave names. The walk is a depth-first
+/// search with an explicit stack, so paths come out in the order of
+/// each cave's edges.
+pub struct Paths<'g, 'a> {
+    graph: &'g Graph<'a>,
+    limits: Vec<(usize, bool)>,
+    visits: Vec<usize>,
+    twice: usize,
+    stack: Vec<Frame>,
 }
 
 #[derive(Debug)]
-/// Specialized graph for this input.
+/// Cave on the current path along with the next edge to follow.
+struct Frame {
+    node: usize,
+    edge: usize,
+    extra: bool,
+}
+
+impl<'g, 'a> Paths<'g, 'a> {
+    /// Start walking paths through the graph.
+    pub fn new(graph: &'g Graph<'a>, rules: &Rules) -> Self {
+        let mut paths = Self {
+            graph,
+            limits: rules.resolve(graph),
+            visits: vec![0; graph.len()],
+            twice: rules.twice,
+            stack: vec![],
+        };
+        match (graph.start, graph.end) {
+            (Some(start), Some(end)) if start != end => paths.enter(start),
+            _ => {}
+        }
+        paths
+    }
+
+    /// Move on to the next path ending at `end`, leaving the current
+    /// path without `end` on the stack. Returns `false` once every path
+    /// has been walked.
+    fn advance(&mut self) -> bool {
+        while let Some(frame) = self.stack.last_mut() {
+            let node = frame.node;
+            let next = match self.graph.edges[node].get(frame.edge) {
+                Some(&next) => next,
+                None => {
+                    self.leave();
+                    continue;
+                }
+            };
+            frame.edge += 1;
+            if Some(next) == self.graph.end {
+                return true;
+            }
+            self.enter(next);
+        }
+        false
+    }
+
+    /// Push the cave onto the path if the rules allow visiting it.
+    fn enter(&mut self, node: usize) {
+        let (limit, extra) = self.limits[node];
+        let visits = self.visits[node];
+        let extra = if visits < limit {
+            false
+        } else if extra && visits == limit && self.twice > 0 {
+            self.twice -= 1;
+            true
+        } else {
+            return;
+        };
+        self.visits[node] += 1;
+        self.stack.push(Frame {
+            node,
+            edge: 0,
+            extra,
+        });
+    }
+
+    /// Pop the last cave from the path.
+    fn leave(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            self.visits[frame.node] -= 1;
+            if frame.extra {
+                self.twice += 1;
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Paths<'_, 'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.advance() {
+            return None;
+        }
+        let caves = self.stack.iter().map(|frame| frame.node);
+        let path = caves.chain(self.graph.end);
+        Some(path.map(|node| self.graph.verts[node]).collect())
+    }
+}
+
+#[derive(Debug)]
+/// Memoised path counting. The paths onward from a cave only depend on
+/// which limited caves have been visited, which of those have been
+/// visited an extra time, and how many extra visits are left, so each
+/// combination is only counted once.
+struct Counter<'g, 'a> {
+    graph: &'g Graph<'a>,
+    limits: Vec<(usize, bool)>,
+    bits: Vec<Option<u128>>,
+    twice: usize,
+    memo: HashMap<(usize, u128, u128, usize), usize>,
+}
+
+impl<'g, 'a> Counter<'g, 'a> {
+    /// Assign a bit to each cave limited to a single visit. Returns
+    /// `None` if any other limit is in place or there are too many
+    /// limited caves to fit in a bitmask.
+    fn new(graph: &'g Graph<'a>, rules: &Rules) -> Option<Self> {
+        let limits = rules.resolve(graph);
+        let mut next = 0;
+        let mut bits = vec![];
+        for &(limit, _) in &limits {
+            bits.push(match limit {
+                0 | usize::MAX => None,
+                1 if next < u128::BITS => {
+                    next += 1;
+                    Some(1 << (next - 1))
+                }
+                _ => return None,
+            });
+        }
+        Some(Self {
+            graph,
+            limits,
+            bits,
+            twice: rules.twice,
+            memo: HashMap::new(),
+        })
+    }
+
+    /// Count every path from `start` to `end`.
+    fn count(&mut self) -> usize {
+        let start = match (self.graph.start, self.graph.end) {
+            (Some(start), Some(end)) if start != end => start,
+            _ => return 0,
+        };
+        let visited = self.bits[start].unwrap_or(0);
+        self.onward(start, visited, 0, self.twice)
+    }
+
+    /// Count paths to `end` from a cave on the path.
+    fn onward(&mut self, node: usize, visited: u128, doubled: u128, twice: usize) -> usize {
+        let key = (node, visited, doubled, twice);
+        if let Some(&count) = self.memo.get(&key) {
+            return count;
+        }
+
+        let graph = self.graph;
+        let mut count = 0;
+        for &next in &graph.edges[node] {
+            if Some(next) == graph.end {
+                count += 1;
+                continue;
+            }
+            let (limit, extra) = self.limits[next];
+            count += match self.bits[next] {
+                None if limit == 0 => 0,
+                None => self.onward(next, visited, doubled, twice),
+                Some(bit) if visited & bit == 0 => self.onward(next, visited | bit, doubled, twice),
+                Some(bit) if extra && doubled & bit == 0 && twice > 0 => {
+                    self.onward(next, visited, doubled | bit, twice - 1)
+                }
+                Some(_) => 0,
+            };
+        }
+
+        self.memo.insert(key, count);
+        count
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Undirected graph of caves, indexed in the order they were added.
+/// Without both a `start` and an `end` cave there are no paths.
 pub struct Graph<'a> {
-    len: usize,
-    start: usize,
-    end: usize,
-    verts: [&'a str; 32],
-    edges: [Vec<usize>; 32],
-    kinds: [Kind; 32],
+    start: Option<usize>,
+    end: Option<usize>,
+    index: HashMap<&'a str, usize>,
+    verts: Vec<&'a str>,
+    edges: Vec<Vec<usize>>,
+    kinds: Vec<Kind>,
 }
 
 impl<'a> Graph<'a> {
     /// Create empty graph.
     pub fn new() -> Self {
-        const EMPTY: Vec<usize> = Vec::new();
         Self {
-            len: 0,
-            start: 0,
-            end: 0,
-            verts: [""; 32],
-            edges: [EMPTY; 32],
-            kinds: [Kind::End; 32],
+            start: None,
+            end: None,
+            index: HashMap::new(),
+            verts: vec![],
+            edges: vec![],
+            kinds: vec![],
         }
     }
 
+    /// Number of vertices in the graph.
+    pub fn len(&self) -> usize {
+        self.verts.len()
+    }
+
+    /// Is the graph empty?
+    pub fn is_empty(&self) -> bool {
+        self.verts.is_empty()
+    }
+
+    /// Look up a vertex's index.
+    pub fn find(&self, key: &str) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
     /// Retrieve a vertex's index, adding it to the graph if necessary.
     pub fn vert(&mut self, key: &'a str) -> usize {
-        match self.verts.iter().position(|&vert| vert == key) {
-            Some(i) => i,
-            None => {
-                let i = self.len;
-                self.verts[i] = key;
-                self.len += 1;
-                let kind = Kind::new(key);
-                if kind == Kind::Start {
-                    self.start = i;
-                }
-                if kind == Kind::End {
-                    self.end = i;
-                }
-                self.kinds[i] = kind;
-                i
-            }
+        if let Some(i) = self.find(key) {
+            return i;
         }
+        let i = self.verts.len();
+        let kind = Kind::new(key);
+        if kind == Kind::Start {
+            self.start = Some(i);
+        }
+        if kind == Kind::End {
+            self.end = Some(i);
+        }
+        self.index.insert(key, i);
+        self.verts.push(key);
+        self.edges.push(vec![]);
+        self.kinds.push(kind);
+        i
     }
 
     /// Create an undirected edge between the two vertex indices.
@@ -142,60 +389,6 @@ impl Kind {
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
-/// How to visit small caves.
-pub enum Mode {
-    /// Each small cave can be visited once.
-    Once,
-    /// A single small cave can be visited twice.
-    Twice,
-}
-
-#[derive(PartialEq, Eq, Debug)]
-/// A path through the graph.
-pub struct Path {
-    len: usize,
-    path: [usize; 32],
-}
-
-impl Path {
-    /// Create an empty path.
-    pub fn new() -> Self {
-        Self {
-            len: 0,
-            path: [0; 32],
-        }
-    }
-
-    #[inline]
-    /// Push a node onto the end of the path.
-    fn push(&mut self, key: usize) {
-        self.path[self.len] = key;
-        self.len += 1;
-    }
-
-    #[inline]
-    /// Pop the last node from the end of the path.
-    fn pop(&mut self) {
-        self.len -= 1;
-    }
-
-    #[inline]
-    /// Count the number of times a node appears in the path.
-    fn occurrences(&self, key: usize) -> usize {
-        self.path[0..self.len]
-            .iter()
-            .filter(|&&node| node == key)
-            .count()
-    }
-}
-
-impl Default for Path {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 fn parse_graph(lines: &[String]) -> Graph<'_> {
     lines.iter().flat_map(|line| line.split_once('-')).fold(
         Graph::new(),
@@ -210,3 +403,93 @@ fn parse_graph(lines: &[String]) -> Graph<'_> {
 
 check!(ex 1 = 10, ex 2 = 36, part 1 = 3761, part 2 = 99138);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn enumerate_paths() {
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        let graph = parse_graph(&lines);
+        let paths = Paths::new(&graph, &Rules::once())
+            .map(|path| path.join(","))
+            .sorted()
+            .collect_vec();
+        let expected = [
+            "start,A,b,A,c,A,end",
+            "start,A,b,A,end",
+            "start,A,b,end",
+            "start,A,c,A,b,A,end",
+            "start,A,c,A,b,end",
+            "start,A,c,A,end",
+            "start,A,end",
+            "start,b,A,c,A,end",
+            "start,b,A,end",
+            "start,b,end",
+        ];
+        assert_eq!(paths, expected);
+        assert_eq!(Paths::new(&graph, &Rules::twice(1)).count(), 36);
+    }
+
+    #[test]
+    fn longest_and_shortest() {
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        let graph = parse_graph(&lines);
+        let longest = longest_path(&graph, &Rules::once()).unwrap();
+        assert_eq!(longest.len(), 7);
+        let shortest = shortest_path(&graph, &Rules::once()).unwrap();
+        assert_eq!(shortest.len(), 3);
+
+        let lines = crate::check::parse_lines("start-a\nb-end");
+        let graph = parse_graph(&lines);
+        assert_eq!(longest_path(&graph, &Rules::once()), None);
+        assert_eq!(shortest_path(&graph, &Rules::once()), None);
+    }
+
+    #[test]
+    fn custom_limits() {
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        let graph = parse_graph(&lines);
+        let count = |rules| count_paths(&graph, &rules);
+        assert_eq!(count(Rules::once().limit("A", 1)), 4);
+        assert_eq!(count(Rules::once().limit("b", 0)), 2);
+        assert_eq!(count(Rules::twice(1).limit("b", 1)), 16);
+        assert_eq!(count(Rules::once().limit("c", 2)), 16);
+        assert_eq!(count(Rules::once().limit("start", 5).limit("end", 0)), 10);
+    }
+
+    #[test]
+    fn many_caves() {
+        // Fifteen diamonds in a row, each giving a choice of two caves.
+        let mut text = vec!["start-x0".to_string(), "x15-end".to_string()];
+        for i in 0..15 {
+            for side in ["l", "r"].iter() {
+                text.push(format!("x{}-{}{}", i, side, i));
+                text.push(format!("{}{}-x{}", side, i, i + 1));
+            }
+        }
+        let graph = parse_graph(&text);
+        assert_eq!(graph.len(), 48);
+        assert_eq!(count_paths(&graph, &Rules::once()), 1 << 15);
+        assert_eq!(Paths::new(&graph, &Rules::once()).count(), 1 << 15);
+
+        // Too many caves to memoise.
+        let mut text = vec!["start-c0".to_string(), "c199-end".to_string()];
+        text.extend((0..199).map(|i| format!("c{}-c{}", i, i + 1)));
+        let graph = parse_graph(&text);
+        assert_eq!(count_paths(&graph, &Rules::once()), 1);
+        assert_eq!(count_paths(&graph, &Rules::twice(1)), 1);
+    }
+
+    #[test]
+    fn missing_start_or_end() {
+        for text in &["a-b\nb-end", "start-a\na-b", "a-b"] {
+            let lines = crate::check::parse_lines(text);
+            let graph = parse_graph(&lines);
+            assert_eq!(count_paths(&graph, &Rules::once()), 0, "{}", text);
+            assert_eq!(Paths::new(&graph, &Rules::twice(1)).count(), 0, "{}", text);
+        }
+    }
+}
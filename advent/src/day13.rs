@@ -1,41 +1,233 @@
+use crate::grid::Grid;
+use crate::ocr::{self, Unreadable};
 use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 /// Fold paper along first line.
 pub fn part1(lines: &[String]) -> usize {
     let (points, folds) = parse(lines);
-    fold(points, &folds[0]).len()
+    let mut paper = Paper::sized_by(points, &folds);
+    fold_clear(&mut paper, &folds[0]);
+    paper.len()
 }
 
-/// Completely fold paper and render result.
+/// Completely fold paper and read the letters the dots spell out.
 pub fn part2(lines: &[String]) -> String {
+    let paper = fold_all(lines);
+    match paper.read() {
+        Ok(text) => text,
+        Err(err) => panic!("{}\n{}", err, paper),
+    }
+}
+
+/// Follow every fold in the instructions.
+fn fold_all(lines: &[String]) -> Paper {
     let (points, folds) = parse(lines);
-    render(folds.iter().fold(points, fold))
+    let mut paper = Paper::sized_by(points, &folds);
+    for fold in &folds {
+        fold_clear(&mut paper, fold);
+    }
+    paper
 }
 
-fn fold(points: Vec<Point>, fold: &Fold) -> Vec<Point> {
-    points
-        .iter()
-        .map(|point| point.fold(fold))
-        .unique()
-        .collect()
-}
-
-fn render(points: Vec<Point>) -> String {
-    let max_x = points.iter().map(|point| point.x).max().unwrap_or(0);
-    let max_y = points.iter().map(|point| point.y).max().unwrap_or(0);
-    let mut out = String::new();
-    for y in 0..=max_y {
-        for x in 0..=max_x {
-            if points.contains(&Point::new(x, y)) {
-                out.push('█');
+/// Fold the paper, panicking if a dot lies on the line, since the
+/// instructions never put one there. Folds off the midpoint are fine.
+fn fold_clear(paper: &mut Paper, fold: &Fold) {
+    if let Err(on_line) = paper.fold(fold) {
+        panic!("{}", on_line);
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Transparent paper with dots on it.
+pub struct Paper {
+    points: Vec<Point>,
+    width: usize,
+    height: usize,
+}
+
+impl Paper {
+    /// Create paper of the given size with dots at these points. The
+    /// paper grows to fit any dot past its edges.
+    pub fn new(points: Vec<Point>, width: usize, height: usize) -> Self {
+        let (right, bottom) = bounds(&points);
+        Self {
+            points: points.into_iter().unique().collect(),
+            width: width.max(right),
+            height: height.max(bottom),
+        }
+    }
+
+    /// Create paper with dots at these points, sized so that the first
+    /// fold along each axis is at the midpoint unless a dot lies
+    /// further out. Along an axis with no folds, the paper ends at the
+    /// last dot.
+    pub fn sized_by(points: Vec<Point>, folds: &[Fold]) -> Self {
+        let (mut width, mut height) = bounds(&points);
+        if let Some(x) = folds.iter().find_map(|fold| fold.x()) {
+            width = width.max(2 * x + 1);
+        }
+        if let Some(y) = folds.iter().find_map(|fold| fold.y()) {
+            height = height.max(2 * y + 1);
+        }
+        Self::new(points, width, height)
+    }
+
+    /// Number of visible dots.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Are there no dots?
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Visible dots.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Fold the bottom or right part of the paper over the rest. If the
+    /// fold line isn't at the midpoint, the longer part sticks out past
+    /// the other's edge, and every dot is shifted so the paper still
+    /// starts at zero. Returns the lengths of both parts in that case.
+    /// Fails without folding if a dot lies on the fold line.
+    pub fn fold(&mut self, fold: &Fold) -> Result<Option<OffCenter>, OnLine> {
+        let on_line = self.points.iter().find(|point| match *fold {
+            Fold::X(x) => point.x == x,
+            Fold::Y(y) => point.y == y,
+        });
+        if let Some(&point) = on_line {
+            return Err(OnLine { fold: *fold, point });
+        }
+
+        let (line, size) = match *fold {
+            Fold::X(x) => (x, &mut self.width),
+            Fold::Y(y) => (y, &mut self.height),
+        };
+        let near = line.min(*size);
+        let far = size.saturating_sub(line + 1);
+        let shift = far.saturating_sub(near);
+        *size = near.max(far);
+
+        let flip = |i: usize| {
+            if i > line {
+                shift + line - (i - line)
             } else {
-                out.push(' ');
+                shift + i
+            }
+        };
+        self.points = self
+            .points
+            .iter()
+            .map(|point| match *fold {
+                Fold::X(_) => Point::new(flip(point.x), point.y),
+                Fold::Y(_) => Point::new(point.x, flip(point.y)),
+            })
+            .unique()
+            .collect();
+
+        if near == far {
+            return Ok(None);
+        }
+        Ok(Some(OffCenter {
+            fold: *fold,
+            near,
+            far,
+        }))
+    }
+
+    /// Raw bitmap of the paper, which is `true` where there's a dot.
+    pub fn bitmap(&self) -> Grid<bool> {
+        let mut bitmap = Grid::filled(self.width, self.height, false);
+        for point in &self.points {
+            if let Some(cell) = bitmap.get_mut((point.y, point.x)) {
+                *cell = true;
+            }
+        }
+        bitmap
+    }
+
+    /// Read the dots as letters in the 4×6 font.
+    pub fn read(&self) -> Result<String, Unreadable> {
+        ocr::read(&self.bitmap())
+    }
+}
+
+impl Display for Paper {
+    /// Draw the dots as `█`, leaving off any blank rows or columns past
+    /// the last dot.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let (width, height) = bounds(&self.points);
+        let bitmap = self.bitmap();
+        for row in 0..height {
+            for col in 0..width {
+                write!(f, "{}", if bitmap[(row, col)] { '█' } else { ' ' })?;
             }
+            writeln!(f)?;
         }
-        out.push('\n');
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// A fold whose line wasn't at the midpoint of the paper.
+pub struct OffCenter {
+    /// The fold.
+    pub fold: Fold,
+    /// Length of the part of the paper before the fold line.
+    pub near: usize,
+    /// Length of the part of the paper after the fold line.
+    pub far: usize,
+}
+
+impl Display for OffCenter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} is off center: {} before the line, {} after",
+            self.fold, self.near, self.far
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// A fold that would put a dot on the crease.
+pub struct OnLine {
+    /// The fold.
+    pub fold: Fold,
+    /// The dot on the fold line.
+    pub point: Point,
+}
+
+impl Display for OnLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "dot at {},{} is on the line of {}",
+            self.point.x, self.point.y, self.fold
+        )
     }
-    out
+}
+
+/// Width and height of the smallest rectangle at the origin containing
+/// every point.
+fn bounds(points: &[Point]) -> (usize, usize) {
+    let width = points.iter().map(|point| point.x + 1).max().unwrap_or(0);
+    let height = points.iter().map(|point| point.y + 1).max().unwrap_or(0);
+    (width, height)
 }
 
 fn parse(lines: &[String]) -> (Vec<Point>, Vec<Fold>) {
@@ -63,15 +255,6 @@ impl Point {
     pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
-
-    /// Transpose a point across the specified axis or return the same point.
-    pub fn fold(self, fold: &Fold) -> Self {
-        match *fold {
-            Fold::X(x) if self.x > x => Self::new(x - (self.x - x), self.y),
-            Fold::Y(y) if self.y > y => Self::new(self.x, y - (self.y - y)),
-            _ => self,
-        }
-    }
 }
 
 impl FromStr for Point {
@@ -88,7 +271,7 @@ impl FromStr for Point {
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 /// Represents a fold across an axis.
 pub enum Fold {
     /// Fold over vertical line x=n.
@@ -97,6 +280,24 @@ pub enum Fold {
     Y(usize),
 }
 
+impl Fold {
+    /// Line of a vertical fold.
+    pub fn x(&self) -> Option<usize> {
+        match *self {
+            Fold::X(x) => Some(x),
+            Fold::Y(_) => None,
+        }
+    }
+
+    /// Line of a horizontal fold.
+    pub fn y(&self) -> Option<usize> {
+        match *self {
+            Fold::X(_) => None,
+            Fold::Y(y) => Some(y),
+        }
+    }
+}
+
 impl FromStr for Fold {
     type Err = !;
 
@@ -115,5 +316,92 @@ impl FromStr for Fold {
     }
 }
 
-check!(ex 1 = 17, ex 2 = DIFF, part 1 = 631, part 2 = DIFF);
+impl Display for Fold {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Fold::X(x) => write!(f, "fold along x={}", x),
+            Fold::Y(y) => write!(f, "fold along y={}", y),
+        }
+    }
+}
+
+check!(ex 1 = 17, part 1 = 631, part 2 = "EFLFJGRF");
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn sized_by_folds() {
+        let points = vec![Point::new(0, 0), Point::new(2, 1)];
+        let paper = Paper::sized_by(points.clone(), &[Fold::Y(3), Fold::X(4), Fold::X(1)]);
+        assert_eq!((paper.width(), paper.height()), (9, 7));
+        let paper = Paper::sized_by(points.clone(), &[Fold::Y(3)]);
+        assert_eq!((paper.width(), paper.height()), (3, 7));
+
+        // Dots past the mirror of the first fold are kept.
+        let paper = Paper::sized_by(points, &[Fold::Y(0), Fold::X(0)]);
+        assert_eq!((paper.width(), paper.height()), (3, 2));
+        assert_eq!(paper.len(), 2);
+        let paper = Paper::new(vec![Point::new(5, 1)], 2, 4);
+        assert_eq!((paper.width(), paper.height()), (6, 4));
+        assert_eq!(paper.len(), 1);
+    }
+
+    #[test]
+    fn example_square() {
+        // The example folds into a square rather than letters, so draw it.
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        let expected = include_str!("../outputs/13/example");
+        assert_eq!(fold_all(&lines).to_string(), expected);
+        assert!(fold_all(&lines).read().is_err());
+    }
+
+    #[test]
+    fn off_center_instructions() {
+        // The second fold is off center, which the paper takes in stride.
+        let lines = crate::check::parse_lines("0,0\n8,0\n3,1\n\nfold along x=4\nfold along x=1");
+        let paper = fold_all(&lines);
+        assert_eq!(paper.width(), 2);
+        assert_eq!(paper.points(), [Point::new(1, 0), Point::new(0, 1)]);
+    }
+
+    #[test]
+    fn off_center() {
+        let mut paper = Paper::new(vec![Point::new(0, 0), Point::new(9, 0)], 10, 1);
+        let off_center = OffCenter {
+            fold: Fold::X(3),
+            near: 3,
+            far: 6,
+        };
+        assert_eq!(paper.fold(&Fold::X(3)), Ok(Some(off_center)));
+        assert_eq!(paper.width(), 6);
+        assert_eq!(paper.points(), [Point::new(3, 0), Point::new(0, 0)]);
+        let off_center = OffCenter {
+            fold: Fold::X(4),
+            near: 4,
+            far: 1,
+        };
+        assert_eq!(paper.fold(&Fold::X(4)), Ok(Some(off_center)));
+    }
+
+    #[test]
+    fn dot_on_line() {
+        let mut paper = Paper::new(vec![Point::new(1, 2), Point::new(0, 4)], 3, 5);
+        let on_line = OnLine {
+            fold: Fold::Y(2),
+            point: Point::new(1, 2),
+        };
+        assert_eq!(paper.fold(&Fold::Y(2)), Err(on_line));
+        assert_eq!(paper.height(), 5);
+        assert_eq!(
+            paper.fold(&Fold::X(1)),
+            Err(OnLine {
+                fold: Fold::X(1),
+                ..on_line
+            })
+        );
+        assert!(paper.fold(&Fold::X(2)).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
+use crate::matrix::{Arithmetic, Matrix};
 use itertools::{Itertools, MinMaxResult};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 /// Evolve the polymer for 10 steps.
 pub fn part1(lines: &[String]) -> usize {
@@ -14,46 +15,116 @@ pub fn part2(lines: &[String]) -> usize {
 }
 
 /// Evolve the polymer for _n_ steps.
-pub fn solve(n: usize, template: &str, rules: &HashMap<(char, char), char>) -> usize {
-    let counts = template.chars().tuple_windows().counts();
-    let counts = (0..n).fold(counts, |counts, _| evolve(counts, rules));
-    let last = template.chars().last().unwrap();
-    diff(last, counts)
+///
+/// # Panics
+///
+/// Panics if any element count overflows.
+pub fn solve(n: u64, template: &str, rules: &HashMap<(char, char), char>) -> usize {
+    let polymer = Polymer::new(template, rules);
+    let histogram = polymer.histogram(n, Arithmetic::Checked).unwrap();
+    diff(&histogram) as usize
 }
 
 /// Find the difference in quantity between the most and least common element.
-pub fn diff(last: char, counts: HashMap<(char, char), usize>) -> usize {
-    let mut totals: HashMap<char, usize> =
-        counts
-            .iter()
-            .fold(HashMap::new(), |mut totals, (&(lhs, _), n)| {
-                *totals.entry(lhs).or_default() += n;
-                totals
-            });
-
-    // Last character is off-by-one since we're counting pairs.
-    *totals.entry(last).or_default() += 1;
-
-    if let MinMaxResult::MinMax(least, most) = totals.values().minmax() {
+pub fn diff(histogram: &BTreeMap<char, u64>) -> u64 {
+    if let MinMaxResult::MinMax(least, most) = histogram.values().minmax() {
         most - least
     } else {
         0
     }
 }
 
-/// Apply a rule for each pair in the input.
-pub fn evolve(
-    counts: HashMap<(char, char), usize>,
-    rules: &HashMap<(char, char), char>,
-) -> HashMap<(char, char), usize> {
-    counts
-        .iter()
-        .fold(HashMap::new(), |mut counts, (&(lhs, rhs), n)| {
-            let x = rules[&(lhs, rhs)];
-            *counts.entry((lhs, x)).or_default() += n;
-            *counts.entry((x, rhs)).or_default() += n;
-            counts
-        })
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Polymer template along with the pairs it can ever contain and a
+/// transition matrix mapping the count of each pair to the counts one
+/// step later.
+pub struct Polymer {
+    template: Vec<char>,
+    pairs: Vec<(char, char)>,
+    step: Matrix,
+}
+
+impl Polymer {
+    /// Find every pair reachable from the template and build the
+    /// transition matrix. Pairs without a rule stay as they are.
+    pub fn new(template: &str, rules: &HashMap<(char, char), char>) -> Self {
+        let template = template.chars().collect_vec();
+        let mut index = HashMap::new();
+        let mut pairs = vec![];
+        let mut queue = template
+            .iter()
+            .copied()
+            .tuple_windows()
+            .collect::<VecDeque<_>>();
+        while let Some(pair) = queue.pop_front() {
+            if index.contains_key(&pair) {
+                continue;
+            }
+            index.insert(pair, pairs.len());
+            pairs.push(pair);
+            if let Some(&x) = rules.get(&pair) {
+                queue.push_back((pair.0, x));
+                queue.push_back((x, pair.1));
+            }
+        }
+
+        let mut step = Matrix::zero(pairs.len());
+        for (i, &(lhs, rhs)) in pairs.iter().enumerate() {
+            let produced = match rules.get(&(lhs, rhs)) {
+                Some(&x) => vec![(lhs, x), (x, rhs)],
+                None => vec![(lhs, rhs)],
+            };
+            for pair in produced {
+                let j = index[&pair];
+                step.set(j, i, step.get(j, i) + 1);
+            }
+        }
+
+        Self {
+            template,
+            pairs,
+            step,
+        }
+    }
+
+    /// Every pair the polymer can contain, in the order used by
+    /// `pair_counts`.
+    pub fn pairs(&self) -> &[(char, char)] {
+        &self.pairs
+    }
+
+    /// Count each pair after _n_ steps. Returns `None` on overflow.
+    pub fn pair_counts(&self, n: u64, arithmetic: Arithmetic) -> Option<Vec<u64>> {
+        let mut counts = vec![0; self.pairs.len()];
+        for pair in self.template.iter().copied().tuple_windows() {
+            let i = self.pairs.iter().position(|&p| p == pair)?;
+            counts[i] = arithmetic.add(counts[i], 1)?;
+        }
+        self.step.pow(n, arithmetic)?.apply(&counts, arithmetic)
+    }
+
+    /// Count each element after _n_ steps. Elements with a count of
+    /// zero are left out, which under modular arithmetic includes any
+    /// count that's a multiple of the modulus. Returns `None` on
+    /// overflow.
+    pub fn histogram(&self, n: u64, arithmetic: Arithmetic) -> Option<BTreeMap<char, u64>> {
+        let mut histogram = BTreeMap::new();
+        let counts = self.pair_counts(n, arithmetic)?;
+        for (&(lhs, _), &count) in self.pairs.iter().zip(&counts) {
+            if count == 0 {
+                continue;
+            }
+            let total = histogram.entry(lhs).or_insert(0);
+            *total = arithmetic.add(*total, count)?;
+        }
+
+        // Last character is off-by-one since we're counting pairs.
+        if let Some(&last) = self.template.last() {
+            let total = histogram.entry(last).or_insert(0);
+            *total = arithmetic.add(*total, 1)?;
+        }
+        Some(histogram)
+    }
 }
 
 fn parse(lines: &[String]) -> (String, HashMap<(char, char), char>) {
@@ -69,3 +140,32 @@ fn parse(lines: &[String]) -> (String, HashMap<(char, char), char>) {
 
 check!(ex 1 = 1588, ex 2 = 2188189693529, part 1 = 2967, part 2 = 3692219987038);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+    use std::num::NonZeroU64;
+
+    #[test]
+    fn modular_histogram() {
+        let (template, rules) = parse(&crate::check::parse_lines(tests::EXAMPLE));
+        let polymer = Polymer::new(&template, &rules);
+        let histogram = |arithmetic| polymer.histogram(10, arithmetic).unwrap();
+        let expected = vec![('B', 1749), ('C', 298), ('H', 161), ('N', 865)];
+        assert_eq!(
+            histogram(Arithmetic::Checked).into_iter().collect_vec(),
+            expected
+        );
+
+        let modulo = Arithmetic::Modulo(NonZeroU64::new(100).unwrap());
+        let expected = vec![('B', 49), ('C', 98), ('H', 61), ('N', 65)];
+        assert_eq!(histogram(modulo).into_iter().collect_vec(), expected);
+
+        let modulo = Arithmetic::Modulo(NonZeroU64::new(1_000_000_007).unwrap());
+        let checked = polymer.histogram(40, Arithmetic::Checked).unwrap();
+        let reduced = polymer.histogram(40, modulo).unwrap();
+        for (element, count) in checked {
+            assert_eq!(reduced[&element], count % 1_000_000_007);
+        }
+    }
+}
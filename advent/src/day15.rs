@@ -1,115 +1,239 @@
-use itertools::Itertools;
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
+use crate::grid::{Grid, Pos};
+use std::fmt::{self, Display, Formatter};
 
 /// Find the cost of the optimal path from starting point to the bottom-right point.
 pub fn part1(lines: &[String]) -> usize {
-    Graph::parse(lines).dijkstra()
+    lowest_risk(&Graph::parse(lines).unwrap())
 }
 
 /// Find the cost of the optimal path from starting point to the bottom-right point in the larger map.
 pub fn part2(lines: &[String]) -> usize {
-    Graph::parse(lines).expand(5).dijkstra()
+    lowest_risk(&Graph::parse(lines).unwrap().expand(5))
+}
+
+/// Total risk of the optimal path from the top-left point to the
+/// bottom-right point.
+fn lowest_risk(graph: &Graph) -> usize {
+    let (risk, _) = graph.dijkstra((0, 0), graph.target()).unwrap();
+    risk
 }
 
 /// A point in a 2D coordinate system.
-pub type Point = (i64, i64);
+pub type Point = Pos;
 
 #[derive(PartialEq, Eq, Debug)]
 /// A mapping from points in 2D space to the cost of traversing them.
+/// The map is made of copies of a single tile repeated down and to the
+/// right, and the cost of each point is computed from the tile as it's
+/// needed.
 pub struct Graph {
-    graph: HashMap<Point, usize>,
+    tile: Grid<u8>,
+    repeat: usize,
 }
 
 impl Graph {
-    /// Parse a graph from the input.
-    pub fn parse(lines: &[String]) -> Self {
-        let graph = lines
-            .iter()
-            .enumerate()
-            .flat_map(|(row, line)| {
-                line.bytes()
-                    .enumerate()
-                    .map(move |(col, byte)| ((row as i64, col as i64), (byte - b'0') as usize))
-            })
-            .collect();
-        Self { graph }
+    /// Largest cost of traversing a single point.
+    const MAX_RISK: usize = 9;
+
+    /// Parse a graph from the input. Every risk must be a digit from 1
+    /// to 9.
+    pub fn parse(lines: &[String]) -> Option<Self> {
+        let tile = Grid::parse(lines, |c| match c.to_digit(10)? {
+            0 => None,
+            risk => Some(risk as u8),
+        })?;
+        Some(Self { tile, repeat: 1 })
     }
 
     /// Find the bottom-right point.
     pub fn target(&self) -> Point {
-        let row = self.graph.keys().map(|&(row, _)| row).max().unwrap();
-        let col = self.graph.keys().map(|&(_, col)| col).max().unwrap();
-        (row, col)
+        let (height, width) = self.dimensions();
+        (height - 1, width - 1)
     }
 
     /// Get the dimensions of the graph as _height × width_.
     pub fn dimensions(&self) -> Point {
-        let (row, col) = self.target();
-        (row + 1, col + 1)
+        (
+            self.tile.height() * self.repeat,
+            self.tile.width() * self.repeat,
+        )
     }
 
     /// Expand the graph down and to the right, increasing each point's
     /// cost every time. If the cost of a point would go over 9, it
     /// wraps back to 1.
     pub fn expand(self, n: usize) -> Self {
-        let n = n as i64;
+        Self {
+            repeat: self.repeat * n,
+            ..self
+        }
+    }
+
+    /// Cost of traversing the point, which must be in bounds.
+    pub fn risk(&self, (row, col): Point) -> usize {
+        let (height, width) = (self.tile.height(), self.tile.width());
+        let risk = self.tile[(row % height, col % width)] as usize;
+        (risk + row / height + col / width - 1) % Self::MAX_RISK + 1
+    }
+
+    /// Neighbors of the point in the cardinal directions.
+    pub fn neighbors(&self, (row, col): Point) -> impl Iterator<Item = Point> {
         let (height, width) = self.dimensions();
-        let graph = (0..n)
-            .cartesian_product(0..n)
-            .flat_map(|(y, x)| {
-                self.graph.iter().map(move |((row, col), &cost)| {
-                    let row = row + height * y;
-                    let col = col + width * x;
-                    let cost = ((cost as i64 + x + y) - 1) % 9 + 1;
-                    ((row, col), cost as usize)
-                })
-            })
-            .collect();
-        Self { graph }
+        let up = row.checked_sub(1).map(|row| (row, col));
+        let left = col.checked_sub(1).map(|col| (row, col));
+        let down = (row + 1 < height).then_some((row + 1, col));
+        let right = (col + 1 < width).then_some((row, col + 1));
+        up.into_iter().chain(left).chain(down).chain(right)
     }
 
-    /// Use Dijkstra's algorithm to find the cost of the optimal path
-    /// from the top-left point to the bottom-right point.
-    pub fn dijkstra(&self) -> usize {
-        let target = self.target();
-
-        // Map point to lowest cost
-        let mut costs: HashMap<_, _> = self
-            .graph
-            .iter()
-            .map(|(&point, _)| (point, usize::MAX))
-            .collect();
-        costs.insert((0, 0), 0);
-
-        // Minimum-cost priority queue. BinaryHeap is a max-heap, so
-        // use Reverse to flip comparisons.
-        let mut queue = BinaryHeap::new();
-        queue.push((Reverse(0), (0, 0)));
-
-        while let Some((Reverse(cost), node @ (row, col))) = queue.pop() {
-            if node == target {
-                return cost;
+    /// Is the point within the graph?
+    pub fn contains(&self, (row, col): Point) -> bool {
+        let (height, width) = self.dimensions();
+        row < height && col < width
+    }
+
+    /// Use Dijkstra's algorithm to find the optimal path from the start
+    /// to the goal. Returns the total risk of the path, which doesn't
+    /// count the start, along with every point on the path. Returns
+    /// `None` if either point is outside the graph.
+    ///
+    /// Since every edge costs at most 9, the priority queue is a ring
+    /// of 10 buckets indexed by cost: every point waiting in the queue
+    /// costs no more than 9 beyond the cheapest one. Costs are kept in
+    /// a flat array indexed in row-major order.
+    pub fn dijkstra(&self, start: Point, goal: Point) -> Option<(usize, Vec<Point>)> {
+        if !self.contains(start) || !self.contains(goal) {
+            return None;
+        }
+
+        let (height, width) = self.dimensions();
+        let index = |(row, col): Point| row * width + col;
+
+        let mut costs = vec![usize::MAX; height * width];
+        let mut prev = vec![None; height * width];
+        let ring = Self::MAX_RISK + 1;
+        let mut buckets = vec![vec![]; ring];
+        let mut queued = 1;
+        costs[index(start)] = 0;
+        buckets[0].push(start);
+
+        let mut cost = 0;
+        while queued > 0 {
+            let bucket = cost % ring;
+            while let Some(node) = buckets[bucket].pop() {
+                queued -= 1;
+                if costs[index(node)] < cost {
+                    continue;
+                }
+                if node == goal {
+                    let mut path = vec![goal];
+                    while let Some(before) = prev[index(path[path.len() - 1])] {
+                        path.push(before);
+                    }
+                    path.reverse();
+                    return Some((cost, path));
+                }
+
+                for neighbor in self.neighbors(node) {
+                    let total = cost + self.risk(neighbor);
+                    if total < costs[index(neighbor)] {
+                        costs[index(neighbor)] = total;
+                        prev[index(neighbor)] = Some(node);
+                        buckets[total % ring].push(neighbor);
+                        queued += 1;
+                    }
+                }
             }
+            cost += 1;
+        }
+        None
+    }
+
+    /// Display the graph with only the points on the path showing
+    /// their risk.
+    pub fn highlight<'a>(&'a self, path: &'a [Point]) -> Highlight<'a> {
+        Highlight { graph: self, path }
+    }
+}
 
-            if cost > costs[&node] {
-                continue;
+#[derive(Debug, Copy, Clone)]
+/// Rendering of a graph with a path highlighted. Points on the path
+/// show their risk and every other point is a `.`.
+pub struct Highlight<'a> {
+    graph: &'a Graph,
+    path: &'a [Point],
+}
+
+impl Display for Highlight<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let Highlight { graph, path } = self;
+        let (height, width) = graph.dimensions();
+        let mut on_path = Grid::filled(width, height, false);
+        for &point in path.iter() {
+            if let Some(cell) = on_path.get_mut(point) {
+                *cell = true;
             }
+        }
 
-            for (dy, dx) in [(-1, 0), (0, -1), (1, 0), (0, 1)] {
-                let neighbor = (row + dy, col + dx);
-                if let Some(neighbor_cost) = self.graph.get(&neighbor) {
-                    let cost = neighbor_cost + cost;
-                    if cost < costs[&neighbor] {
-                        queue.push((Reverse(cost), neighbor));
-                        costs.insert(neighbor, cost);
-                    }
+        for row in 0..height {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for col in 0..width {
+                if on_path[(row, col)] {
+                    write!(f, "{}", graph.risk((row, col)))?;
+                } else {
+                    write!(f, ".")?;
                 }
             }
         }
-        usize::MAX
+        Ok(())
     }
 }
 
 check!(ex 1 = 40, ex 2 = 315, part 1 = 589, part 2 = 2885);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn zero_risk() {
+        assert!(Graph::parse(&["19".to_string(), "91".to_string()]).is_some());
+        assert!(Graph::parse(&["19".to_string(), "90".to_string()]).is_none());
+    }
+
+    #[test]
+    fn dijkstra_path() {
+        let lines = crate::check::parse_lines("1911\n1919\n1119");
+        let graph = Graph::parse(&lines).unwrap();
+        let (risk, path) = graph.dijkstra((0, 0), (0, 3)).unwrap();
+        assert_eq!(risk, 7);
+        assert_eq!(
+            path,
+            vec![
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (1, 2),
+                (0, 2),
+                (0, 3)
+            ]
+        );
+        assert_eq!(graph.highlight(&path).to_string(), "1.11\n1.1.\n111.");
+        assert_eq!(graph.dijkstra((0, 0), (3, 0)), None);
+        assert_eq!(graph.dijkstra((1, 1), (1, 1)), Some((0, vec![(1, 1)])));
+    }
+
+    #[test]
+    fn expanded_path() {
+        let graph = Graph::parse(&["9".to_string()]).unwrap().expand(2);
+        let (risk, path) = graph.dijkstra((0, 0), (1, 1)).unwrap();
+        assert_eq!(risk, 3);
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(graph.highlight(&path).to_string(), "91\n.2");
+    }
+}
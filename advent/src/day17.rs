@@ -1,3 +1,4 @@
+use crate::cuboid::Cuboid;
 use itertools::Itertools;
 use sscanf::scanf;
 
@@ -16,22 +17,22 @@ pub fn part2(lines: &[String]) -> usize {
 /// Calculate the sequence of velocities that will launch a probe into the target area.
 pub fn velocities(area: Area) -> impl Iterator<Item = (Velocity, i64)> {
     // Beyond target area after 1 step
-    let dxmax = area.xmax;
-    let dymin = area.ymin;
-    let dymax = -area.ymin;
+    let dxmax = area.xmax();
+    let dymin = area.ymin();
+    let dymax = -area.ymin();
 
     (1..=dxmax)
         .cartesian_product(dymin..=dymax)
         .filter_map(move |(dx, dy)| {
             let velocity = Point::new(dx, dy);
-            simulate(area, velocity).map(|y| (velocity, y))
+            simulate(&area, velocity).map(|y| (velocity, y))
         })
 }
 
 /// Simulate a probe being launched at the target area using the starting velocity.
 ///
 /// Returns the highest _y_ if the target area was hit.
-pub fn simulate(area: Area, mut velocity: Velocity) -> Option<i64> {
+pub fn simulate(area: &Area, mut velocity: Velocity) -> Option<i64> {
     let mut point = Point::origin();
     let mut max_y = point.y;
     while !point.beyond(area) {
@@ -77,18 +78,15 @@ impl Point {
     }
 
     /// Is this point beyond the target area?
-    fn beyond(&self, area: Area) -> bool {
-        self.x > area.xmax || self.y < area.ymin
+    fn beyond(&self, area: &Area) -> bool {
+        self.x > area.xmax() || self.y < area.ymin()
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 /// Target area defined by its bottom-left and top-right corners.
 pub struct Area {
-    xmin: i64,
-    xmax: i64,
-    ymin: i64,
-    ymax: i64,
+    bounds: Cuboid<2>,
 }
 
 impl Area {
@@ -97,16 +95,23 @@ impl Area {
         let (xmin, xmax, ymin, ymax) =
             scanf!(input, "target area: x={}..{}, y={}..{}", i64, i64, i64, i64).unwrap();
         Self {
-            xmin,
-            xmax,
-            ymin,
-            ymax,
+            bounds: Cuboid::new([xmin..=xmax, ymin..=ymax]),
         }
     }
 
     /// Does the target area contain this point?
     pub fn contains(&self, point: Point) -> bool {
-        self.xmin <= point.x && point.x <= self.xmax && self.ymin <= point.y && point.y <= self.ymax
+        self.bounds.contains([point.x, point.y])
+    }
+
+    /// Rightmost _x_ in the target area.
+    fn xmax(&self) -> i64 {
+        *self.bounds.side(0).end()
+    }
+
+    /// Lowest _y_ in the target area.
+    fn ymin(&self) -> i64 {
+        *self.bounds.side(1).start()
     }
 }
 
@@ -1,6 +1,6 @@
+use crate::grid::Grid;
 use itertools::{iterate, Itertools};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
 use std::convert::TryInto;
 
 /// Iterate the image enhancement algorithm twice.
@@ -47,91 +47,36 @@ impl Palette {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-/// A point in a 2D coordinate system
-pub struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl Point {
-    /// Create a new point.
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-/// Rectangular bounds represented as the origin and bottom-right corner.
-pub struct Bounds {
-    origin: Point,
-    extent: Point,
-}
-
-impl Bounds {
-    /// Create new bounds.
-    fn new(xmin: i64, ymin: i64, xmax: i64, ymax: i64) -> Self {
-        Self {
-            origin: Point::new(xmin, ymin),
-            extent: Point::new(xmax, ymax),
-        }
-    }
-
-    /// Expand the bounds one unit in every direction.
-    fn pad(&self) -> Self {
-        let xmin = self.origin.x - 1;
-        let ymin = self.origin.y - 1;
-        let xmax = self.extent.x + 1;
-        let ymax = self.extent.y + 1;
-        Self::new(xmin, ymin, xmax, ymax)
-    }
-}
-
 #[derive(Debug)]
-/// Image to enhance.
+/// Image to enhance. Pixels outside the grid all have the default
+/// value.
 pub struct Image {
-    pixels: HashMap<(i64, i64), bool>,
+    pixels: Grid<bool>,
     default: bool,
-    bounds: Bounds,
 }
 
 impl Image {
     /// Parse an image from a sequence of strings.
     pub fn parse(lines: &[String]) -> Self {
-        let height = lines.len() as i64;
-        let width = lines[0].len() as i64;
-        let bounds = Bounds::new(0, 0, width - 1, height - 1);
-
-        let pixels = lines
-            .iter()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.chars()
-                    .enumerate()
-                    .map(move |(j, c)| ((i as i64, j as i64), c == '#'))
-            })
-            .collect();
-
+        let pixels = Grid::parse(lines, |c| Some(c == '#')).unwrap();
         Self {
             pixels,
             default: false,
-            bounds,
         }
     }
 
     /// Sample each pixel and its nine neighbors to create a new image
+    /// padded by one pixel in every direction.
     pub fn enhance(&self, palette: &Palette) -> Self {
-        let bounds = self.bounds.pad();
-        let (ymin, ymax) = (bounds.origin.y, bounds.extent.y);
-        let (xmin, xmax) = (bounds.origin.x, bounds.extent.x);
-
-        let pixels = (ymin..=ymax)
-            .cartesian_product(xmin..=xmax)
-            .map(|(i, j)| {
-                let index = self.sample(i, j).fold(0, |n, lit| n << 1 | (lit as i64));
-                ((i, j), palette.pixel(index))
-            })
-            .collect();
+        let width = self.pixels.width() + 2;
+        let height = self.pixels.height() + 2;
+        let mut pixels = Grid::filled(width, height, false);
+        for (i, j) in pixels.positions() {
+            let index = self
+                .sample(i as isize - 1, j as isize - 1)
+                .fold(0, |n, lit| n << 1 | (lit as i64));
+            pixels[(i, j)] = palette.pixel(index);
+        }
 
         let default = if self.default {
             palette.pixel(0b111111111 - 1)
@@ -139,30 +84,27 @@ impl Image {
             palette.pixel(0)
         };
 
-        Self {
-            pixels,
-            bounds,
-            default,
-        }
+        Self { pixels, default }
     }
 
     /// Yield each of the nine pixels in the pixel's neighborhood.
-    pub fn sample(&self, i: i64, j: i64) -> impl Iterator<Item = bool> + '_ {
-        SAMPLE
-            .iter()
-            .map(move |(di, dj)| self.pixels.get(&(i + di, j + dj)).unwrap_or(&self.default))
-            .copied()
+    pub fn sample(&self, i: isize, j: isize) -> impl Iterator<Item = bool> + '_ {
+        SAMPLE.iter().map(move |(di, dj)| {
+            self.pixels
+                .checked(i + di, j + dj)
+                .map_or(self.default, |pos| self.pixels[pos])
+        })
     }
 
     /// Count the number of lit pixels
     pub fn lit_pixels(&self) -> usize {
-        self.pixels.values().filter(|&&lit| lit).count()
+        self.pixels.cells().iter().filter(|&&lit| lit).count()
     }
 }
 
 lazy_static! {
     /// Area around a point to sample.
-    pub static ref SAMPLE: [(i64, i64); 9] = (-1..=1)
+    pub static ref SAMPLE: [(isize, isize); 9] = (-1..=1)
         .cartesian_product(-1..=1)
         .collect_vec()
         .try_into()
@@ -1,16 +1,17 @@
+use crate::cuboid::Cuboid;
 use itertools::Itertools;
 use sscanf::scanf;
 use std::ops::RangeInclusive;
 
 /// Volume of enabled prisms within a 100-unit cube.
 pub fn part1(lines: &[String]) -> usize {
-    let bounds = Prism::cube(false, -50..=50);
-    solve(&parse(lines, &Some(bounds)))
+    let reactor = Reactor::from_steps(&parse(lines));
+    reactor.volume_within(&Cuboid::cube(-50..=50))
 }
 
 /// Volume of all enabled prisms.
 pub fn part2(lines: &[String]) -> usize {
-    solve(&parse(lines, &None))
+    solve(&parse(lines))
 }
 
 /// For each enabled prism compute its volume minus the volume of any
@@ -27,48 +28,67 @@ pub fn solve(prisms: &[Prism]) -> usize {
     total
 }
 
-/// Add some interval-like functionality to a type
-pub trait Interval {
-    /// Result of intersect
-    type Intersection;
+#[derive(Debug, Clone, Default)]
+/// Lit cubes in the reactor, stored as a set of disjoint boxes.
+pub struct Reactor {
+    lit: Vec<Cuboid<3>>,
+}
 
-    /// Length of interval.
-    fn length(&self) -> usize;
+impl Reactor {
+    /// Run every reboot step in order starting from an unlit reactor.
+    pub fn from_steps(steps: &[Prism]) -> Self {
+        let mut reactor = Self::default();
+        for step in steps {
+            reactor.apply(step);
+        }
+        reactor
+    }
 
-    /// Compute the intersection of an interval
-    fn intersect(&self, other: &Self) -> Self::Intersection;
-}
+    /// Turn the prism's cubes on or off. Any lit box overlapping the
+    /// step is carved into the pieces outside it, so the set stays
+    /// disjoint.
+    pub fn apply(&mut self, step: &Prism) {
+        self.lit = Cuboid::carve(std::mem::take(&mut self.lit), &step.bounds);
+        if step.on {
+            self.lit.push(step.bounds.clone());
+        }
+    }
 
-impl Interval for RangeInclusive<i64> {
-    type Intersection = Option<Self>;
+    /// Is the cube at this position lit?
+    pub fn is_lit(&self, x: i64, y: i64, z: i64) -> bool {
+        self.lit.iter().any(|lit| lit.contains([x, y, z]))
+    }
 
-    fn length(&self) -> usize {
-        (self.end() - self.start() + 1).abs() as usize
+    /// Number of lit cubes within the query box.
+    pub fn volume_within(&self, bounds: &Cuboid<3>) -> usize {
+        self.lit
+            .iter()
+            .filter_map(|lit| lit.intersect(bounds))
+            .map(|lit| lit.volume())
+            .sum()
     }
 
-    fn intersect(&self, other: &Self) -> Self::Intersection {
-        if other.end() < self.start() || self.end() < other.start() {
-            return None;
-        }
-        let start = *self.start().max(other.start());
-        let end = *self.end().min(other.end());
-        Some(start..=end)
+    /// Total number of lit cubes.
+    pub fn volume(&self) -> usize {
+        self.lit.iter().map(Cuboid::volume).sum()
+    }
+
+    /// Disjoint boxes covering every lit cube.
+    pub fn lit(&self) -> &[Cuboid<3>] {
+        &self.lit
     }
 }
 
 #[derive(Debug, Clone)]
-/// Axis-aligned rectangular prism
+/// Reboot step turning an axis-aligned rectangular prism on or off
 pub struct Prism {
     on: bool,
-    x: RangeInclusive<i64>,
-    y: RangeInclusive<i64>,
-    z: RangeInclusive<i64>,
+    bounds: Cuboid<3>,
 }
 
 impl Prism {
-    /// Parse a prism from a string, optionally restricting the result
-    /// to be within some boundaries.
-    pub fn parse(line: &str, bounds: &Option<Prism>) -> Option<Self> {
+    /// Parse a prism from a string.
+    pub fn parse(line: &str) -> Option<Self> {
         let (on, xmin, xmax, ymin, ymax, zmin, zmax) = scanf!(
             line,
             "{/on|off/} x={}..{},y={}..{},z={}..{}",
@@ -81,26 +101,17 @@ impl Prism {
             i64
         )?;
 
-        let prism = Self {
+        Some(Self {
             on: on == "on",
-            x: xmin..=xmax,
-            y: ymin..=ymax,
-            z: zmin..=zmax,
-        };
-
-        if let Some(bounds) = bounds {
-            return prism.intersect(bounds);
-        }
-        Some(prism)
+            bounds: Cuboid::new([xmin..=xmax, ymin..=ymax, zmin..=zmax]),
+        })
     }
 
     /// Create a prism with equal-length sides
     pub fn cube(on: bool, interval: RangeInclusive<i64>) -> Self {
         Self {
             on,
-            x: interval.clone(),
-            y: interval.clone(),
-            z: interval,
+            bounds: Cuboid::cube(interval),
         }
     }
 
@@ -108,15 +119,13 @@ impl Prism {
     pub fn intersect(&self, other: &Prism) -> Option<Self> {
         Some(Self {
             on: self.on,
-            x: self.x.intersect(&other.x)?,
-            y: self.y.intersect(&other.y)?,
-            z: self.z.intersect(&other.z)?,
+            bounds: self.bounds.intersect(&other.bounds)?,
         })
     }
 
     /// Compute the volume of the prism.
     fn volume(&self) -> usize {
-        self.x.length() * self.y.length() * self.z.length()
+        self.bounds.volume()
     }
 
     /// Compute the volume of the prism without counting the volume of
@@ -135,12 +144,35 @@ impl Prism {
     }
 }
 
-fn parse(lines: &[String], bounds: &Option<Prism>) -> Vec<Prism> {
+fn parse(lines: &[String]) -> Vec<Prism> {
     lines
         .iter()
-        .filter_map(|line| Prism::parse(line, bounds))
+        .filter_map(|line| Prism::parse(line))
         .collect_vec()
 }
 
 check!(ex 1 = 474140, ex 2 = 2758514936282235, part 1 = 591365, part 2 = 1211172281877240);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    #[test]
+    fn reactor() {
+        let reactor = Reactor::from_steps(&[
+            Prism::cube(true, 0..=3),
+            Prism::cube(false, 1..=2),
+            Prism::cube(true, 10..=10),
+        ]);
+        assert!(reactor.is_lit(0, 0, 0));
+        assert!(reactor.is_lit(3, 2, 1));
+        assert!(!reactor.is_lit(1, 2, 2));
+        assert!(reactor.is_lit(10, 10, 10));
+        assert!(!reactor.is_lit(4, 4, 4));
+        assert_eq!(reactor.volume(), 64 - 8 + 1);
+        assert_eq!(reactor.volume_within(&Cuboid::cube(0..=1)), 8 - 1);
+        assert_eq!(reactor.volume_within(&Cuboid::cube(5..=20)), 1);
+        assert_eq!(reactor.volume_within(&Cuboid::cube(20..=30)), 0);
+    }
+}
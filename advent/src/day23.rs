@@ -1,123 +1,96 @@
+use crate::search::{self, Search};
 use itertools::Itertools;
-use sscanf::scanf;
-use std::cmp::{Ordering, Reverse};
-use std::collections::{BinaryHeap, HashMap};
-use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
 
 /// Compute the least amount of energy necessary to organize the
 /// amphipods in their rooms.
 pub fn part1(lines: &[String]) -> usize {
-    solve(State::<2>::parse(lines).unwrap())
+    let (burrow, state) = Burrow::parse(lines).unwrap();
+    let (cost, _path) = burrow.solve(state).unwrap();
+    cost
 }
 
 /// Compute the least amount of energy necessary to organize the
 /// amphipods in their rooms after adding more of this lil guys.
 pub fn part2(lines: &[String]) -> usize {
-    let mut lines = lines.iter().cloned().collect_vec();
-    lines.splice(3..3, ["  #D#C#B#A#".into(), "  #D#B#A#C#".into()]);
-    solve(State::<4>::parse(&lines).unwrap())
+    let lines = UNFOLD.apply(lines);
+    let (burrow, state) = Burrow::parse(&lines).unwrap();
+    let (cost, _path) = burrow.solve(state).unwrap();
+    cost
 }
 
-/// Dijkstra's algorithm to find the smallest cost to move from the
-/// initial state to an organized state.
-pub fn solve<const N: usize>(state: State<N>) -> usize {
-    let mut seen = HashMap::new();
-    let mut queue = BinaryHeap::new();
-    queue.push((Reverse(0), Opaque(state)));
-
-    while let Some((Reverse(cost), Opaque(state))) = queue.pop() {
-        if state.organized() {
-            return cost;
-        }
+/// The folded-up part of the diagram revealed in part 2.
+pub const UNFOLD: Unfold = Unfold {
+    after: 1,
+    rows: &["  #D#C#B#A#", "  #D#B#A#C#"],
+};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Rule for unfolding the diagram by inserting extra rows into the
+/// rooms.
+pub struct Unfold<'a> {
+    /// Number of rows of the rooms to keep above the inserted rows.
+    pub after: usize,
+    /// Rows to insert, in the same format as the diagram.
+    pub rows: &'a [&'a str],
+}
 
-        for (new_cost, other) in state.step() {
-            let total_cost = new_cost + cost;
-            let previous_cost = seen.entry(other).or_insert(usize::MAX);
-            if total_cost < *previous_cost {
-                *previous_cost = total_cost;
-                queue.push((Reverse(total_cost), Opaque(other)));
-            }
-        }
+impl Unfold<'_> {
+    /// Insert the rows into the diagram. The first two lines of the
+    /// diagram are the top wall and the hallway.
+    pub fn apply(&self, lines: &[String]) -> Vec<String> {
+        let mut lines = lines.to_vec();
+        let at = 2 + self.after;
+        lines.splice(at..at, self.rows.iter().map(|&row| row.to_string()));
+        lines
     }
-
-    0
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
-/// Some kind of critter, really just node labels.
-pub enum Amphipod {
-    /// Amber
-    A,
-    /// Bronze
-    B,
-    /// Copper
-    C,
-    /// Desert
-    D,
-}
+/// Some kind of critter, really just node labels. Kinds are labeled
+/// with consecutive letters starting from `A`, and each kind's
+/// destination is the room with the same index counting from the left.
+pub struct Amphipod(u8);
 
 impl Amphipod {
     /// Parse an amphipod from a character.
     pub fn parse(c: char) -> Option<Self> {
-        match c {
-            'A' => Some(Self::A),
-            'B' => Some(Self::B),
-            'C' => Some(Self::C),
-            'D' => Some(Self::D),
-            _ => None,
+        if c.is_ascii_uppercase() {
+            return Some(Self(c as u8 - b'A'));
         }
+        None
     }
 
-    /// Compute the cost to move this amphipod the number of steps.
-    pub fn cost(&self, steps: usize) -> usize {
-        steps
-            * match self {
-                Self::A => 1,
-                Self::B => 10,
-                Self::C => 100,
-                Self::D => 1000,
-            }
+    /// Character used to draw this amphipod.
+    pub fn label(&self) -> char {
+        (b'A' + self.0) as char
     }
 
     /// Return this amphipod's destination room index.
     pub fn room(&self) -> usize {
-        match self {
-            Self::A => 0,
-            Self::B => 1,
-            Self::C => 2,
-            Self::D => 3,
-        }
+        self.0 as usize
     }
+}
 
-    /// Sequence of amphipods ordered from left-to-right.
-    pub fn each() -> [Amphipod; 4] {
-        [Self::A, Self::B, Self::C, Self::D]
+impl Display for Amphipod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.label())
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
-/// A room containing no more than N amphipods.
-pub struct Room<const N: usize> {
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+/// A room containing a fixed number of slots for amphipods.
+pub struct Room {
     kind: Amphipod,
-    slots: [Option<Amphipod>; N],
+    slots: Vec<Option<Amphipod>>,
 }
 
-impl<const N: usize> Room<N> {
-    /// Initial sequence of empty rooms.
-    pub fn rooms() -> [Self; 4] {
-        Amphipod::each()
-            .iter()
-            .copied()
-            .map(Self::new)
-            .collect_vec()
-            .try_into()
-            .unwrap()
-    }
-
-    /// Create an empty room as a destination for the specified kind
-    /// of amphipod.
-    pub fn new(kind: Amphipod) -> Self {
-        let slots = [None; N];
+impl Room {
+    /// Create an empty room with the given depth as a destination for
+    /// the specified kind of amphipod.
+    pub fn new(kind: Amphipod, depth: usize) -> Self {
+        let slots = vec![None; depth];
         Self { kind, slots }
     }
 
@@ -159,7 +132,7 @@ impl<const N: usize> Room<N> {
     /// Attempt to move an amphipod out of this room, returning the
     /// cost to move out into the hallway.
     pub fn pop(&mut self) -> Option<(usize, Amphipod)> {
-        for i in 0..N {
+        for i in 0..self.slots.len() {
             let has_others = self.has_others_from(i + 1);
             if let Some(pod) = self.slots[i].take() {
                 if pod != self.kind || has_others {
@@ -187,81 +160,318 @@ impl<const N: usize> Room<N> {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
-/// Cave state consists of the hallway and 4 rooms.
-pub struct State<const N: usize> {
-    hall: [Option<Amphipod>; 11],
-    rooms: [Room<N>; 4],
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+/// Cave state consists of the hallway and the rooms.
+pub struct State {
+    hall: Vec<Option<Amphipod>>,
+    rooms: Vec<Room>,
 }
 
-impl<const N: usize> State<N> {
+impl State {
     /// Create a new state.
-    pub fn new(hall: [Option<Amphipod>; 11], rooms: [Room<N>; 4]) -> Self {
+    pub fn new(hall: Vec<Option<Amphipod>>, rooms: Vec<Room>) -> Self {
         Self { hall, rooms }
     }
 
-    /// Parse the state from a sequence of lines.
-    pub fn parse(lines: &[String]) -> Option<State<N>> {
-        let mut rooms = Room::rooms();
-        for (i, line) in lines[2..2 + N].iter().enumerate() {
-            let (_, a, b, c, d, _) = scanf!(
-                line,
-                "{/[# ]+/}{}#{}#{}#{}{/[# ]+/}",
-                String,
-                char,
-                char,
-                char,
-                char,
-                String
-            )?;
-            for (j, &x) in [a, b, c, d].iter().enumerate() {
-                let pod = Amphipod::parse(x)?;
-                rooms[j].put(i, pod);
+    /// Is every room full of the right kind of amphipod?
+    pub fn organized(&self) -> bool {
+        self.rooms.iter().all(|room| room.organized())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Layout of the burrow: the width of the hallway, where each room
+/// opens onto the hallway, how deep the rooms are, and how much energy
+/// each kind of amphipod uses per step.
+pub struct Burrow {
+    hall: usize,
+    doors: Vec<usize>,
+    depth: usize,
+    costs: Vec<usize>,
+}
+
+impl Burrow {
+    /// Parse the layout and initial state from the diagram. Any row
+    /// below the hallway with a letter or empty space in it is part of
+    /// the rooms, and a room's threshold is the hallway tile directly
+    /// above it. There's one kind of amphipod per room, and each kind
+    /// uses 10 times as much energy per step as the kind before it, so
+    /// layouts with too many rooms for those costs to fit are rejected.
+    pub fn parse(lines: &[String]) -> Result<(Self, State), String> {
+        let tile = |c: char| match c {
+            '.' => Some(None),
+            c => Amphipod::parse(c).map(Some),
+        };
+        let no_room = |pod: Amphipod| format!("no room for amphipod {}", pod);
+
+        let hall_line = lines.get(1).ok_or("missing hallway")?;
+        let hall = hall_line
+            .chars()
+            .skip(1)
+            .take_while(|&c| c != '#')
+            .map(tile)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("invalid hallway {}", hall_line))?;
+
+        let rows = lines
+            .get(2..)
+            .unwrap_or_default()
+            .iter()
+            .take_while(|line| line.chars().any(|c| tile(c).is_some()))
+            .collect_vec();
+        let doors = rows
+            .first()
+            .ok_or("missing rooms")?
+            .chars()
+            .positions(|c| tile(c).is_some())
+            .map(|col| col - 1)
+            .collect_vec();
+        if let Some(door) = doors.iter().find(|&&door| door >= hall.len()) {
+            return Err(format!("room at column {} is past the hallway", door + 1));
+        }
+        if let Some(&pod) = hall.iter().flatten().find(|pod| pod.room() >= doors.len()) {
+            return Err(no_room(pod));
+        }
+
+        let depth = rows.len();
+        let mut rooms = (0..doors.len())
+            .map(|i| Room::new(Amphipod(i as u8), depth))
+            .collect_vec();
+        for (i, row) in rows.iter().enumerate() {
+            let row = row.chars().collect_vec();
+            for (j, &door) in doors.iter().enumerate() {
+                let c = row.get(door + 1).copied().unwrap_or(' ');
+                let spot = tile(c).ok_or_else(|| format!("invalid room tile {:?}", c))?;
+                if let Some(pod) = spot {
+                    if pod.room() >= doors.len() {
+                        return Err(no_room(pod));
+                    }
+                    rooms[j].put(i, pod);
+                }
+            }
+        }
+
+        let costs = (0..doors.len() as u32)
+            .map(|i| 10usize.checked_pow(i))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("too many rooms ({})", doors.len()))?;
+        let burrow = Self {
+            hall: hall.len(),
+            doors,
+            depth,
+            costs,
+        };
+        Ok((burrow, State::new(hall, rooms)))
+    }
+
+    /// Replace the energy used per step by each kind of amphipod. Fails
+    /// unless there's exactly one cost per room.
+    pub fn with_costs(mut self, costs: Vec<usize>) -> Result<Self, String> {
+        if costs.len() != self.doors.len() {
+            return Err(format!(
+                "expected {} costs but got {}",
+                self.doors.len(),
+                costs.len()
+            ));
+        }
+        self.costs = costs;
+        Ok(self)
+    }
+
+    /// Compute the cost to move this amphipod the number of steps.
+    pub fn cost(&self, pod: Amphipod, steps: usize) -> usize {
+        steps * self.costs[pod.room()]
+    }
+
+    /// Index of the hall tile at the threshold of this amphipod's
+    /// destination room.
+    pub fn door(&self, pod: Amphipod) -> usize {
+        self.doors[pod.room()]
+    }
+
+    /// Draw the state in the same format as the input diagram.
+    pub fn diagram<'a>(&'a self, state: &'a State) -> Diagram<'a> {
+        Diagram {
+            burrow: self,
+            state,
+        }
+    }
+
+    /// Energy used by the cheapest single move from one state to the
+    /// other, if there is such a move.
+    pub fn move_cost(&self, from: &State, to: &State) -> Option<usize> {
+        self.step(from)
+            .into_iter()
+            .filter(|(_, state)| state == to)
+            .map(|(cost, _)| cost)
+            .min()
+    }
+
+    /// Print each state on the path along with the energy used to get
+    /// there from the previous state.
+    pub fn replay<W: Write>(&self, path: &[State], mut output: W) -> io::Result<()> {
+        let mut total = 0;
+        for (i, state) in path.iter().enumerate() {
+            if i == 0 {
+                writeln!(output, "Start")?;
+            } else {
+                let cost = self.move_cost(&path[i - 1], state).unwrap_or(0);
+                total += cost;
+                writeln!(output, "Move {}: {} energy (total {})", i, cost, total)?;
             }
+            writeln!(output, "{}", self.diagram(state))?;
+        }
+        Ok(())
+    }
+
+    /// A* search to find the smallest cost to move from the initial
+    /// state to an organized state. Returns that cost along with every
+    /// state on the way from the initial state to the organized state,
+    /// or `None` if the amphipods can't be organized.
+    pub fn solve(&self, state: State) -> Option<(usize, Vec<State>)> {
+        if self.packable() {
+            let compact = Compact::new(self);
+            let (cost, path) = search::astar(&compact, self.pack(&state))?;
+            Some((cost, path.into_iter().map(|key| self.unpack(key)).collect()))
+        } else {
+            search::astar(self, state)
         }
-        Some(State::new([None; 11], rooms))
+    }
+
+    /// Number of tiles in the hallway and rooms.
+    fn tiles(&self) -> usize {
+        self.hall + self.doors.len() * self.depth
+    }
+
+    /// Can every state in this burrow be packed into a `u128`? Each
+    /// tile takes 3 bits, so there can be at most 42 tiles and 7 kinds
+    /// of amphipod.
+    pub fn packable(&self) -> bool {
+        self.tiles() * Packed::BITS <= 128 && self.doors.len() < 1 << Packed::BITS
+    }
+
+    /// Pack the state into 3 bits per tile, hallway first and then each
+    /// room from top to bottom. Rooms don't need to store their kind
+    /// since that's determined by their position.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug mode if the burrow isn't `packable`.
+    pub fn pack(&self, state: &State) -> Packed {
+        debug_assert!(self.packable());
+        let tiles = state
+            .hall
+            .iter()
+            .chain(state.rooms.iter().flat_map(|room| room.slots.iter()));
+        let bits = tiles.rev().fold(0u128, |bits, tile| {
+            let code = tile.map_or(0, |pod| pod.0 as u128 + 1);
+            bits << Packed::BITS | code
+        });
+        Packed(bits)
+    }
+
+    /// Unpack a state packed by `pack`.
+    pub fn unpack(&self, packed: Packed) -> State {
+        let mut bits = packed.0;
+        let mut next = || {
+            let code = (bits & Packed::MASK) as u8;
+            bits >>= Packed::BITS;
+            code.checked_sub(1).map(Amphipod)
+        };
+        let hall = (0..self.hall).map(|_| next()).collect_vec();
+        let rooms = (0..self.doors.len())
+            .map(|i| Room {
+                kind: Amphipod(i as u8),
+                slots: (0..self.depth).map(|_| next()).collect(),
+            })
+            .collect_vec();
+        State::new(hall, rooms)
+    }
+
+    /// Same as `estimate`, but read straight from the packed tiles.
+    pub fn estimate_packed(&self, packed: Packed) -> usize {
+        let tile = |i: usize| {
+            let code = (packed.0 >> (i * Packed::BITS) & Packed::MASK) as u8;
+            code.checked_sub(1).map(Amphipod)
+        };
+
+        let hall: usize = (0..self.hall)
+            .filter_map(|i| tile(i).map(|pod| self.cost(pod, hall_dist(i, self.door(pod)) + 1)))
+            .sum();
+
+        let mut rooms = 0;
+        for (room, &door) in self.doors.iter().enumerate() {
+            let top = self.hall + room * self.depth;
+            // Walk up from the bottom, remembering whether any slot below
+            // holds another kind.
+            let mut others = false;
+            for i in (0..self.depth).rev() {
+                if let Some(pod) = tile(top + i) {
+                    if pod.room() != room {
+                        rooms += self.cost(pod, i + 1 + hall_dist(door, self.door(pod)) + 1);
+                        others = true;
+                    } else if others {
+                        rooms += self.cost(pod, i + 1 + 2 + 1);
+                    }
+                }
+            }
+        }
+
+        hall + rooms
+    }
+
+    /// The only organized state: an empty hallway with every room full
+    /// of its own kind.
+    fn organized(&self) -> State {
+        let rooms = (0..self.doors.len())
+            .map(|i| Room {
+                kind: Amphipod(i as u8),
+                slots: vec![Some(Amphipod(i as u8)); self.depth],
+            })
+            .collect();
+        State::new(vec![None; self.hall], rooms)
     }
 
     /// Sequence of valid states reachable from this state along with
     /// their associated costs.
-    pub fn step(&self) -> Vec<(usize, Self)> {
-        let mut states: Vec<(usize, Self)> = Vec::new();
+    pub fn step(&self, state: &State) -> Vec<(usize, State)> {
+        let mut states: Vec<(usize, State)> = Vec::new();
 
-        for (i, _room) in self.rooms.iter().enumerate() {
+        for (i, _room) in state.rooms.iter().enumerate() {
             // Hall to room
-            for (j, &spot) in self.hall.iter().enumerate() {
+            for (j, &spot) in state.hall.iter().enumerate() {
                 if let Some(pod) = spot {
-                    if let Some(cost) = self.hall_to_room(j, i) {
-                        let mut copy = *self;
+                    if let Some(cost) = self.hall_to_room(state, j, i) {
+                        let mut copy = state.clone();
                         if let Some(enter) = copy.rooms[i].push(pod) {
                             copy.hall[j] = None;
-                            states.push((pod.cost(cost + enter), copy))
+                            states.push((self.cost(pod, cost + enter), copy))
                         }
                     }
                 }
             }
 
-            let mut copy = *self;
+            let mut copy = state.clone();
             if let Some((exit, pod)) = copy.rooms[i].pop() {
                 // Room to room
                 for (j, _room) in copy.rooms.iter().enumerate() {
                     if i == j {
                         continue;
                     }
-                    if let Some(enter) = copy.room_to_room(i, j) {
-                        let mut copy = copy;
+                    if let Some(enter) = self.room_to_room(&copy, i, j) {
+                        let mut copy = copy.clone();
                         if let Some(cost) = copy.rooms[j].push(pod) {
-                            states.push((pod.cost(exit + cost + enter), copy))
+                            states.push((self.cost(pod, exit + cost + enter), copy))
                         }
                     }
                 }
 
                 // Room to hall
                 for (j, _spot) in copy.hall.iter().enumerate() {
-                    if let Some(cost) = copy.room_to_hall(i, j) {
-                        let mut copy = copy;
+                    if let Some(cost) = self.room_to_hall(&copy, i, j) {
+                        let mut copy = copy.clone();
                         copy.hall[j] = Some(pod);
-                        states.push((pod.cost(exit + cost), copy))
+                        states.push((self.cost(pod, exit + cost), copy))
                     }
                 }
             }
@@ -270,48 +480,61 @@ impl<const N: usize> State<N> {
         states
     }
 
-    /// Is every room full of the right kind of amphipod?
-    pub fn organized(&self) -> bool {
-        self.rooms.iter().all(|room| room.organized())
+    /// Lower bound on the energy needed to organize the amphipods:
+    /// each one has to at least walk to its room's threshold and take
+    /// one step in, ignoring any other amphipods in the way. Amphipods
+    /// of the right kind that are blocking others in still have to step
+    /// out and back in.
+    pub fn estimate(&self, state: &State) -> usize {
+        let hall: usize = state
+            .hall
+            .iter()
+            .enumerate()
+            .filter_map(|(i, spot)| {
+                spot.map(|pod| self.cost(pod, hall_dist(i, self.door(pod)) + 1))
+            })
+            .sum();
+
+        let mut rooms = 0;
+        for (room, &door) in state.rooms.iter().zip(&self.doors) {
+            for (i, slot) in room.slots.iter().enumerate() {
+                if let Some(pod) = *slot {
+                    if pod != room.kind {
+                        rooms += self.cost(pod, i + 1 + hall_dist(door, self.door(pod)) + 1);
+                    } else if room.has_others_from(i + 1) {
+                        rooms += self.cost(pod, i + 1 + 2 + 1);
+                    }
+                }
+            }
+        }
+
+        hall + rooms
     }
 
     /// Cost to move from one room to another, if possible.
-    fn room_to_room(&self, src: usize, dest: usize) -> Option<usize> {
-        self.dist(2 * src + 2, 2 * dest + 2, true)
+    fn room_to_room(&self, state: &State, src: usize, dest: usize) -> Option<usize> {
+        self.dist(state, self.doors[src], self.doors[dest], true)
     }
 
     /// Cost to move from the hall to a room, if possible.
-    fn hall_to_room(&self, src: usize, dest: usize) -> Option<usize> {
-        self.dist(src, 2 * dest + 2, true)
+    fn hall_to_room(&self, state: &State, src: usize, dest: usize) -> Option<usize> {
+        self.dist(state, src, self.doors[dest], true)
     }
 
     /// Cost to move from a room to the hall, if possible.
-    fn room_to_hall(&self, src: usize, dest: usize) -> Option<usize> {
-        self.dist(2 * src + 2, dest, false)
+    fn room_to_hall(&self, state: &State, src: usize, dest: usize) -> Option<usize> {
+        self.dist(state, self.doors[src], dest, false)
     }
 
     /// Cost to move from the source to the destination, if possible.
     /// The final argument determines whether or not the destination is
     /// allowed to be the threshold of a room.
-    ///
-    /// Note that a room's index can be converted to its threshold's
-    /// tile's index by doubling it and adding 2:
-    ///
-    /// ```plaintext
-    /// room:       0     1     2     3
-    ///           x 2   x 2   x 2   x 2
-    ///           = 0   = 2   = 4   = 6
-    ///           + 2   + 2   + 2   + 2
-    ///           = 2   = 4   = 6   = 8
-    /// --------------------------------------
-    /// hall: 0  1  2  3  4  5  6  7  8  9  10
-    /// ```
-    fn dist(&self, src: usize, dest: usize, to_room: bool) -> Option<usize> {
+    fn dist(&self, state: &State, src: usize, dest: usize, to_room: bool) -> Option<usize> {
         if src == dest {
             return None;
         }
 
-        if !to_room && [2, 4, 6, 8].contains(&dest) {
+        if !to_room && self.doors.contains(&dest) {
             return None;
         }
 
@@ -321,7 +544,7 @@ impl<const N: usize> State<N> {
             if i == src {
                 continue;
             }
-            if self.hall[i].is_some() {
+            if state.hall[i].is_some() {
                 return None;
             }
         }
@@ -330,29 +553,238 @@ impl<const N: usize> State<N> {
 }
 
 #[derive(Debug, Copy, Clone)]
-/// A value that does not contribute to a composite value's equality
-/// or ordering. It is always equal to other values of the same type.
-pub struct Opaque<A>(A);
+/// A state drawn with the layout of its burrow. Parsing the diagram
+/// with `Burrow::parse` produces the same state.
+pub struct Diagram<'a> {
+    burrow: &'a Burrow,
+    state: &'a State,
+}
 
-impl<A> PartialEq for Opaque<A> {
-    fn eq(&self, _: &Self) -> bool {
-        true
+impl Display for Diagram<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let Diagram { burrow, state } = self;
+        let tile = |tile: &Option<Amphipod>| tile.map_or('.', |pod| pod.label());
+        let width = burrow.hall + 2;
+        let first = burrow.doors[0] + 1;
+        let last = burrow.doors[burrow.doors.len() - 1] + 1;
+
+        writeln!(f, "{}", "#".repeat(width))?;
+        writeln!(f, "#{}#", state.hall.iter().map(tile).join(""))?;
+        for i in 0..burrow.depth {
+            let (start, end) = if i == 0 {
+                (0, width)
+            } else {
+                (first - 1, last + 2)
+            };
+            let row = (0..end)
+                .map(
+                    |col| match burrow.doors.iter().position(|&door| door + 1 == col) {
+                        Some(j) => tile(&state.rooms[j].slots[i]),
+                        None if col < start => ' ',
+                        None => '#',
+                    },
+                )
+                .join("");
+            writeln!(f, "{}", row)?;
+        }
+        write!(
+            f,
+            "{}{}",
+            " ".repeat(first - 1),
+            "#".repeat(last - first + 3)
+        )
     }
 }
 
-impl<A> Eq for Opaque<A> {}
+impl Search for Burrow {
+    type State = State;
 
-impl<A> PartialOrd for Opaque<A> {
-    fn partial_cmp(&self, _: &Self) -> Option<Ordering> {
-        Some(Ordering::Equal)
+    fn successors(&self, state: &State) -> Vec<(usize, State)> {
+        self.step(state)
+    }
+
+    fn goal(&self, state: &State) -> bool {
+        state.organized()
+    }
+
+    fn estimate(&self, state: &State) -> usize {
+        Burrow::estimate(self, state)
     }
 }
 
-impl<A> Ord for Opaque<A> {
-    fn cmp(&self, _: &Self) -> Ordering {
-        Ordering::Equal
+#[derive(Debug, Copy, Clone)]
+/// Search a `packable` burrow with states stored as `Packed` keys. Keys
+/// are only unpacked to generate their moves.
+struct Compact<'a> {
+    burrow: &'a Burrow,
+    goal: Packed,
+}
+
+impl<'a> Compact<'a> {
+    fn new(burrow: &'a Burrow) -> Self {
+        let goal = burrow.pack(&burrow.organized());
+        Self { burrow, goal }
     }
 }
 
+impl Search for Compact<'_> {
+    type State = Packed;
+
+    fn successors(&self, &packed: &Packed) -> Vec<(usize, Packed)> {
+        let burrow = self.burrow;
+        let state = burrow.unpack(packed);
+        burrow
+            .step(&state)
+            .iter()
+            .map(|(cost, other)| (*cost, burrow.pack(other)))
+            .collect()
+    }
+
+    fn goal(&self, &packed: &Packed) -> bool {
+        packed == self.goal
+    }
+
+    fn estimate(&self, &packed: &Packed) -> usize {
+        self.burrow.estimate_packed(packed)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+/// Compact encoding of a `State` for hashing and queue storage. Each
+/// tile takes 3 bits: zero for empty, otherwise the amphipod's kind
+/// plus one.
+pub struct Packed(u128);
+
+impl Packed {
+    /// Bits per tile.
+    const BITS: usize = 3;
+
+    /// Mask for a single tile.
+    const MASK: u128 = (1 << Self::BITS) - 1;
+}
+
+/// Number of tiles between two hall positions.
+fn hall_dist(src: usize, dest: usize) -> usize {
+    src.max(dest) - src.min(dest)
+}
+
 check!(ex 1 = 12521, ex 2 = 44169, part 1 = 18282, part 2 = 50132);
 bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    /// A burrow with a hallway just wide enough for `kinds` rooms, each
+    /// holding one amphipod.
+    fn diagram(kinds: &str) -> Vec<String> {
+        let wall = "#".repeat(2 * kinds.len() + 5);
+        let hall = ".".repeat(2 * kinds.len() + 3);
+        vec![
+            wall.clone(),
+            format!("#{}#", hall),
+            format!("###{}###", kinds.chars().join("#")),
+            wall,
+        ]
+    }
+
+    #[test]
+    fn too_many_rooms() {
+        // The 21st kind would use 10^20 energy per step.
+        let kinds = ('A'..='U').collect::<String>();
+        assert!(Burrow::parse(&diagram(&kinds[..20])).is_ok());
+        assert_eq!(
+            Burrow::parse(&diagram(&kinds)).map(|_| ()),
+            Err("too many rooms (21)".to_string())
+        );
+    }
+
+    #[test]
+    fn with_costs() {
+        let (burrow, state) = Burrow::parse(&diagram("BA")).unwrap();
+        // A steps aside past B's room, since parking between the rooms
+        // would block B.
+        assert_eq!(burrow.solve(state.clone()).unwrap().0, 46);
+        assert!(burrow.clone().with_costs(vec![1]).is_err());
+        // Now it's cheaper for B to step aside instead.
+        let burrow = burrow.with_costs(vec![100, 1]).unwrap();
+        assert_eq!(burrow.solve(state).unwrap().0, 406);
+    }
+
+    #[test]
+    fn diagram_round_trip() {
+        let lines = crate::check::parse_lines(tests::EXAMPLE);
+        let (burrow, state) = Burrow::parse(&lines).unwrap();
+        let drawn = burrow.diagram(&state).to_string();
+        assert_eq!(
+            drawn.lines().map(str::trim_end).collect_vec(),
+            lines.iter().map(|line| line.trim_end()).collect_vec()
+        );
+        let lines = drawn.lines().map(String::from).collect_vec();
+        assert_eq!(Burrow::parse(&lines), Ok((burrow, state)));
+    }
+
+    #[test]
+    fn replay() {
+        let (burrow, state) = Burrow::parse(&diagram("BA")).unwrap();
+        let (_cost, path) = burrow.solve(state).unwrap();
+        let mut output = vec![];
+        burrow.replay(&path, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let expected = "\
+Start
+#########
+#.......#
+###B#A###
+  #####
+Move 1: 4 energy (total 4)
+#########
+#.A.....#
+###B#.###
+  #####
+Move 2: 40 energy (total 44)
+#########
+#.A.....#
+###.#B###
+  #####
+Move 3: 2 energy (total 46)
+#########
+#.......#
+###A#B###
+  #####
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn bad_diagrams() {
+        let parse = |text: &str| Burrow::parse(&crate::check::parse_lines(text)).map(|_| ());
+        assert_eq!(parse("#####"), Err("missing hallway".to_string()));
+        assert_eq!(
+            parse("#####\n#.x.#"),
+            Err("invalid hallway #.x.#".to_string())
+        );
+        assert_eq!(
+            parse("#####\n#...#\n#####"),
+            Err("missing rooms".to_string())
+        );
+        assert_eq!(
+            parse("#####\n#.C.#\n##A##"),
+            Err("no room for amphipod C".to_string())
+        );
+    }
+
+    #[test]
+    fn packed_search() {
+        let lines = UNFOLD.apply(&crate::check::parse_lines(tests::EXAMPLE));
+        let (burrow, state) = Burrow::parse(&lines).unwrap();
+        let compact = Compact::new(&burrow);
+        let (_cost, path) = burrow.solve(state).unwrap();
+        for state in &path {
+            let packed = burrow.pack(state);
+            assert_eq!(burrow.estimate_packed(packed), burrow.estimate(state));
+            assert_eq!(compact.goal(&packed), state.organized());
+        }
+        assert!(compact.goal(&burrow.pack(&path[path.len() - 1])));
+    }
+}
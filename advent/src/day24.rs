@@ -1,19 +1,36 @@
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, Write};
 use std::str::FromStr;
 
 /// Find the largest 14-digit number with no zero digits accepted by
 /// the program.
 pub fn part1(lines: &[String]) -> i64 {
     let input = (1..=9).rev().collect_vec();
-    solve(&parse(lines), &input).unwrap()
+    model_number(&parse(lines), &input).unwrap()
 }
 
 /// Find the smallest 14-digit number with no zero digits accepted by
 /// the program.
 pub fn part2(lines: &[String]) -> i64 {
     let input = (1..=9).collect_vec();
-    solve(&parse(lines), &input).unwrap()
+    model_number(&parse(lines), &input).unwrap()
+}
+
+/// Derive the model number from the program's digit constraints if the
+/// program looks like MONAD, otherwise fall back to searching.
+fn model_number(program: &[Op], input: &[i64]) -> Option<i64> {
+    let compiled = Compiled::new(program);
+    Monad::analyze(program)
+        .and_then(|monad| monad.solve(input))
+        .filter(|&n| compiled.validate(n))
+        .or_else(|| solve(&compiled, input))
+}
+
+/// Check whether the program accepts the model number.
+pub fn validate(program: &[Op], model_number: i64) -> bool {
+    Compiled::new(program).validate(model_number)
 }
 
 fn parse(lines: &[String]) -> Vec<Op> {
@@ -57,6 +74,18 @@ impl FromStr for Reg {
     }
 }
 
+impl Display for Reg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let c = match self {
+            Reg::W => 'w',
+            Reg::X => 'x',
+            Reg::Y => 'y',
+            Reg::Z => 'z',
+        };
+        write!(f, "{}", c)
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 /// Right-hand-operand to an instruction.
 pub enum Val {
@@ -91,6 +120,15 @@ impl FromStr for Val {
     }
 }
 
+impl Display for Val {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Val::Reg(reg) => write!(f, "{}", reg),
+            Val::Imm(imm) => write!(f, "{}", imm),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 /// ALU instructions.
 pub enum Op {
@@ -135,6 +173,87 @@ impl FromStr for Op {
     }
 }
 
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Op::Inp(acc) => write!(f, "inp {}", acc),
+            Op::Add(acc, val) => write!(f, "add {} {}", acc, val),
+            Op::Mul(acc, val) => write!(f, "mul {} {}", acc, val),
+            Op::Div(acc, val) => write!(f, "div {} {}", acc, val),
+            Op::Mod(acc, val) => write!(f, "mod {} {}", acc, val),
+            Op::Eql(acc, val) => write!(f, "eql {} {}", acc, val),
+        }
+    }
+}
+
+impl Op {
+    /// Execute any instruction other than `inp` against the registers.
+    /// `pc` is only used to report errors.
+    fn apply(&self, pc: usize, registers: &mut [i64; 4]) -> Result<(), AluError> {
+        match *self {
+            Op::Inp(_) => {}
+            Op::Add(acc, val) => {
+                registers[acc.idx()] = add(pc, registers[acc.idx()], val.fetch(registers))?
+            }
+            Op::Mul(acc, val) => {
+                registers[acc.idx()] = mul(pc, registers[acc.idx()], val.fetch(registers))?
+            }
+            Op::Div(acc, val) => {
+                registers[acc.idx()] = div(pc, registers[acc.idx()], val.fetch(registers))?
+            }
+            Op::Mod(acc, val) => {
+                registers[acc.idx()] = rem(pc, registers[acc.idx()], val.fetch(registers))?
+            }
+            Op::Eql(acc, val) => {
+                registers[acc.idx()] = (registers[acc.idx()] == val.fetch(registers)) as i64
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Instructions the ALU refuses to execute.
+pub enum AluError {
+    /// `div` with a zero right-hand operand.
+    DivideByZero {
+        /// Address of the instruction.
+        pc: usize,
+    },
+    /// `mod` with a zero right-hand operand.
+    ModuloByZero {
+        /// Address of the instruction.
+        pc: usize,
+    },
+    /// `mod` with a negative operand.
+    NegativeModulo {
+        /// Address of the instruction.
+        pc: usize,
+        /// Left-hand operand.
+        lhs: i64,
+        /// Right-hand operand.
+        rhs: i64,
+    },
+    /// `add`, `mul`, or `div` whose result doesn't fit in 64 bits.
+    Overflow {
+        /// Address of the instruction.
+        pc: usize,
+    },
+}
+
+impl Display for AluError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            AluError::DivideByZero { pc } => write!(f, "division by zero at {}", pc),
+            AluError::ModuloByZero { pc } => write!(f, "modulo by zero at {}", pc),
+            AluError::NegativeModulo { pc, lhs, rhs } => {
+                write!(f, "negative modulo {} % {} at {}", lhs, rhs, pc)
+            }
+            AluError::Overflow { pc } => write!(f, "overflow at {}", pc),
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Does the ALU need input or has it halted?
 pub enum Progress {
@@ -144,35 +263,640 @@ pub enum Progress {
     Done,
 }
 
-/// Run the ALU starting until it halts or requests more input. Mutates
-/// the argument program counter and registers.
-fn step(program: &[Op], pc: &mut usize, registers: &mut [i64; 4], input: i64) -> Progress {
-    let mut input = Some(input);
-    while let Some(op) = program.get(*pc) {
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Bytecode instruction with its operands resolved to register indices
+/// or immediates, so the interpreter never has to call `Val::fetch`.
+enum Code {
+    Inp(usize),
+    Set(usize, i64),
+    AddR(usize, usize),
+    AddI(usize, i64),
+    MulR(usize, usize),
+    MulI(usize, i64),
+    DivR(usize, usize),
+    DivI(usize, i64),
+    ModR(usize, usize),
+    ModI(usize, i64),
+    EqlR(usize, usize),
+    EqlI(usize, i64),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// ALU program lowered to flat bytecode. Each instruction compiles to
+/// exactly one `Code`, so addresses are unchanged.
+pub struct Compiled {
+    code: Vec<Code>,
+}
+
+impl Compiled {
+    /// Lower the program to bytecode. `mul r 0` becomes `r = 0`, and
+    /// `div r 1` becomes a no-op `add r 0`.
+    pub fn new(program: &[Op]) -> Self {
+        let code = program
+            .iter()
+            .map(|op| match *op {
+                Op::Inp(acc) => Code::Inp(acc.idx()),
+                Op::Add(acc, Val::Reg(reg)) => Code::AddR(acc.idx(), reg.idx()),
+                Op::Add(acc, Val::Imm(imm)) => Code::AddI(acc.idx(), imm),
+                Op::Mul(acc, Val::Imm(0)) => Code::Set(acc.idx(), 0),
+                Op::Mul(acc, Val::Reg(reg)) => Code::MulR(acc.idx(), reg.idx()),
+                Op::Mul(acc, Val::Imm(imm)) => Code::MulI(acc.idx(), imm),
+                Op::Div(acc, Val::Imm(1)) => Code::AddI(acc.idx(), 0),
+                Op::Div(acc, Val::Reg(reg)) => Code::DivR(acc.idx(), reg.idx()),
+                Op::Div(acc, Val::Imm(imm)) => Code::DivI(acc.idx(), imm),
+                Op::Mod(acc, Val::Reg(reg)) => Code::ModR(acc.idx(), reg.idx()),
+                Op::Mod(acc, Val::Imm(imm)) => Code::ModI(acc.idx(), imm),
+                Op::Eql(acc, Val::Reg(reg)) => Code::EqlR(acc.idx(), reg.idx()),
+                Op::Eql(acc, Val::Imm(imm)) => Code::EqlI(acc.idx(), imm),
+            })
+            .collect_vec();
+        Self { code }
+    }
+
+    /// Run the ALU starting until it halts or requests more input.
+    /// Mutates the argument program counter and registers.
+    pub fn step(
+        &self,
+        pc: &mut usize,
+        registers: &mut [i64; 4],
+        input: i64,
+    ) -> Result<Progress, AluError> {
+        let mut input = Some(input);
+        while let Some(&code) = self.code.get(*pc) {
+            match code {
+                Code::Inp(acc) => match input.take() {
+                    Some(input) => registers[acc] = input,
+                    None => return Ok(Progress::More),
+                },
+                Code::Set(acc, imm) => registers[acc] = imm,
+                Code::AddR(acc, reg) => registers[acc] = add(*pc, registers[acc], registers[reg])?,
+                Code::AddI(acc, imm) => registers[acc] = add(*pc, registers[acc], imm)?,
+                Code::MulR(acc, reg) => registers[acc] = mul(*pc, registers[acc], registers[reg])?,
+                Code::MulI(acc, imm) => registers[acc] = mul(*pc, registers[acc], imm)?,
+                Code::DivR(acc, reg) => registers[acc] = div(*pc, registers[acc], registers[reg])?,
+                Code::DivI(acc, imm) => registers[acc] = div(*pc, registers[acc], imm)?,
+                Code::ModR(acc, reg) => registers[acc] = rem(*pc, registers[acc], registers[reg])?,
+                Code::ModI(acc, imm) => registers[acc] = rem(*pc, registers[acc], imm)?,
+                Code::EqlR(acc, reg) => registers[acc] = (registers[acc] == registers[reg]) as i64,
+                Code::EqlI(acc, imm) => registers[acc] = (registers[acc] == imm) as i64,
+            }
+            *pc += 1;
+        }
+        Ok(Progress::Done)
+    }
+
+    /// Check whether the program accepts the model number: it must
+    /// have no zero digits, the program must read every digit, and it
+    /// must store zero in the Z register.
+    pub fn validate(&self, model_number: i64) -> bool {
+        if model_number <= 0 {
+            return false;
+        }
+        let digits = model_number
+            .to_string()
+            .bytes()
+            .map(|b| (b - b'0') as i64)
+            .collect_vec();
+        if digits.contains(&0) {
+            return false;
+        }
+
+        let mut pc = 0;
+        let mut registers = [0; 4];
+        for (i, &digit) in digits.iter().enumerate() {
+            match self.step(&mut pc, &mut registers, digit) {
+                Ok(Progress::More) => continue,
+                Ok(Progress::Done) => return i == digits.len() - 1 && registers[Reg::Z.idx()] == 0,
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+}
+
+/// `lhs / rhs`, rejecting division by zero and overflow.
+#[inline]
+fn div(pc: usize, lhs: i64, rhs: i64) -> Result<i64, AluError> {
+    if rhs == 0 {
+        return Err(AluError::DivideByZero { pc });
+    }
+    lhs.checked_div(rhs).ok_or(AluError::Overflow { pc })
+}
+
+/// `lhs + rhs`, rejecting overflow.
+#[inline]
+fn add(pc: usize, lhs: i64, rhs: i64) -> Result<i64, AluError> {
+    lhs.checked_add(rhs).ok_or(AluError::Overflow { pc })
+}
+
+/// `lhs * rhs`, rejecting overflow.
+#[inline]
+fn mul(pc: usize, lhs: i64, rhs: i64) -> Result<i64, AluError> {
+    lhs.checked_mul(rhs).ok_or(AluError::Overflow { pc })
+}
+
+/// `lhs % rhs`, rejecting zero or negative operands.
+#[inline]
+fn rem(pc: usize, lhs: i64, rhs: i64) -> Result<i64, AluError> {
+    if rhs == 0 {
+        return Err(AluError::ModuloByZero { pc });
+    }
+    if lhs < 0 || rhs < 0 {
+        return Err(AluError::NegativeModulo { pc, lhs, rhs });
+    }
+    Ok(lhs % rhs)
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Comparison used by register breakpoints.
+pub enum Cmp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl Cmp {
+    /// Compare two values.
+    pub fn test(&self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Cmp::Eq => lhs == rhs,
+            Cmp::Ne => lhs != rhs,
+            Cmp::Lt => lhs < rhs,
+            Cmp::Le => lhs <= rhs,
+            Cmp::Gt => lhs > rhs,
+            Cmp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl FromStr for Cmp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "==" => Ok(Cmp::Eq),
+            "!=" => Ok(Cmp::Ne),
+            "<" => Ok(Cmp::Lt),
+            "<=" => Ok(Cmp::Le),
+            ">" => Ok(Cmp::Gt),
+            ">=" => Ok(Cmp::Ge),
+            _ => Err(format!("unrecognized comparison {}", s)),
+        }
+    }
+}
+
+impl Display for Cmp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let s = match self {
+            Cmp::Eq => "==",
+            Cmp::Ne => "!=",
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Condition that pauses the debugger.
+pub enum Breakpoint {
+    /// Pause before executing the instruction at this address.
+    Pc(usize),
+    /// Pause after an instruction changes the register so that it
+    /// satisfies the comparison when it didn't before.
+    Reg(Reg, Cmp, i64),
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    /// Parse either an address, e.g. `17`, or a register condition, e.g.
+    /// `z == 0`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split_whitespace().collect_vec();
+        match &parts[..] {
+            [pc] => pc
+                .parse()
+                .map(Breakpoint::Pc)
+                .map_err(|_| format!("invalid address {}", pc)),
+            [reg, cmp, val] => {
+                let reg = reg.parse()?;
+                let cmp = cmp.parse()?;
+                let val = val.parse().map_err(|_| format!("invalid value {}", val))?;
+                Ok(Breakpoint::Reg(reg, cmp, val))
+            }
+            _ => Err(format!("unrecognized breakpoint {}", s)),
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "pc {}", pc),
+            Breakpoint::Reg(reg, cmp, val) => write!(f, "{} {} {}", reg, cmp, val),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Registers after executing a single instruction.
+pub struct Trace {
+    /// Address of the instruction.
+    pub pc: usize,
+    /// The instruction.
+    pub op: Op,
+    /// `[w, x, y, z]` after executing the instruction.
+    pub registers: [i64; 4],
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let [w, x, y, z] = self.registers;
+        write!(
+            f,
+            "{:>4}  {:<12} w={} x={} y={} z={}",
+            self.pc,
+            self.op.to_string(),
+            w,
+            x,
+            y,
+            z
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// Why the debugger stopped running.
+pub enum Stop {
+    /// Hit a breakpoint.
+    Break(Breakpoint),
+    /// The next instruction is `inp` but there's no queued input.
+    Input,
+    /// Ran off the end of the program.
+    Halt,
+}
+
+#[derive(Debug, Clone)]
+/// Single-stepping ALU interpreter that records a trace of every
+/// instruction it executes.
+pub struct Debugger<'a> {
+    program: &'a [Op],
+    pc: usize,
+    registers: [i64; 4],
+    input: VecDeque<i64>,
+    breakpoints: Vec<Breakpoint>,
+    trace: Vec<Trace>,
+    /// Address of the breakpoint that stopped the last `run`, until the
+    /// instruction there executes.
+    paused: Option<usize>,
+}
+
+impl<'a> Debugger<'a> {
+    /// Start debugging the program with zeroed registers.
+    pub fn new(program: &'a [Op]) -> Self {
+        Self {
+            program,
+            pc: 0,
+            registers: [0; 4],
+            input: VecDeque::new(),
+            breakpoints: vec![],
+            trace: vec![],
+            paused: None,
+        }
+    }
+
+    /// Restart the program, keeping breakpoints but dropping queued
+    /// input and the trace.
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.registers = [0; 4];
+        self.input.clear();
+        self.trace.clear();
+        self.paused = None;
+    }
+
+    /// Queue values to be read by `inp` instructions.
+    pub fn feed<I: IntoIterator<Item = i64>>(&mut self, input: I) {
+        self.input.extend(input)
+    }
+
+    /// Add a breakpoint.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint)
+    }
+
+    /// Remove all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear()
+    }
+
+    /// Current breakpoints.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Address of the next instruction.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Current `[w, x, y, z]`.
+    pub fn registers(&self) -> [i64; 4] {
+        self.registers
+    }
+
+    /// Every instruction executed since the last reset.
+    pub fn trace(&self) -> &[Trace] {
+        &self.trace
+    }
+
+    /// Execute the next instruction. Returns `None` without doing
+    /// anything if the program has halted or needs input.
+    pub fn step(&mut self) -> Result<Option<Trace>, AluError> {
+        let op = match self.program.get(self.pc) {
+            Some(&op) => op,
+            None => return Ok(None),
+        };
+
         match op {
-            Op::Inp(acc) => {
-                if let Some(input) = input.take() {
-                    registers[acc.idx()] = input;
-                } else {
-                    return Progress::More;
+            Op::Inp(acc) => match self.input.pop_front() {
+                Some(input) => self.registers[acc.idx()] = input,
+                None => return Ok(None),
+            },
+            op => op.apply(self.pc, &mut self.registers)?,
+        }
+
+        let trace = Trace {
+            pc: self.pc,
+            op,
+            registers: self.registers,
+        };
+        self.trace.push(trace);
+        self.pc += 1;
+        self.paused = None;
+        Ok(Some(trace))
+    }
+
+    /// Execute instructions until a breakpoint is hit, the program
+    /// needs input, or the program halts. Address breakpoints are
+    /// checked before each instruction, except that calling `run` again
+    /// resumes past the breakpoint that stopped it.
+    pub fn run(&mut self) -> Result<Stop, AluError> {
+        loop {
+            let bp = Breakpoint::Pc(self.pc);
+            if self.paused != Some(self.pc) && self.breakpoints.contains(&bp) {
+                self.paused = Some(self.pc);
+                return Ok(Stop::Break(bp));
+            }
+
+            let before = self.registers;
+            if self.step()?.is_none() {
+                return Ok(self.stopped());
+            }
+
+            let after = self.registers;
+            let hit = self.breakpoints.iter().find(|bp| match bp {
+                Breakpoint::Reg(reg, cmp, val) => {
+                    !cmp.test(before[reg.idx()], *val) && cmp.test(after[reg.idx()], *val)
                 }
+                Breakpoint::Pc(_) => false,
+            });
+            if let Some(&bp) = hit {
+                return Ok(Stop::Break(bp));
             }
-            Op::Add(acc, val) => registers[acc.idx()] += val.fetch(registers),
-            Op::Mul(acc, val) => registers[acc.idx()] *= val.fetch(registers),
-            Op::Div(acc, val) => registers[acc.idx()] /= val.fetch(registers),
-            Op::Mod(acc, val) => registers[acc.idx()] %= val.fetch(registers),
-            Op::Eql(acc, val) => {
-                registers[acc.idx()] = (registers[acc.idx()] == val.fetch(registers)) as i64
+        }
+    }
+
+    /// Explain why `step` couldn't execute anything.
+    fn stopped(&self) -> Stop {
+        if self.pc < self.program.len() {
+            Stop::Input
+        } else {
+            Stop::Halt
+        }
+    }
+}
+
+/// Help text for `repl`.
+const HELP: &str = "\
+commands:
+  step [n]               execute the next n instructions (default 1)
+  continue               run until a breakpoint, input, or halt
+  input <n>...           queue values for inp instructions
+  break <pc>             pause before the instruction at pc
+  break <reg> <cmp> <n>  pause when a register satisfies the comparison
+  delete                 remove all breakpoints
+  regs                   print the registers
+  trace                  print every instruction executed so far
+  reset                  restart the program
+  quit                   exit";
+
+/// Interactive debugger. Reads one command per line from `input` and
+/// writes the results to `output`. Every command except `reset` can be
+/// abbreviated to its first letter.
+pub fn repl<R: BufRead, W: Write>(program: &[Op], input: R, mut output: W) -> io::Result<()> {
+    let mut debugger = Debugger::new(program);
+    write!(output, "(alu) ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let (cmd, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let args = args.trim();
+        match cmd {
+            "" => {}
+            "s" | "step" => {
+                let n = if args.is_empty() { Ok(1) } else { args.parse() };
+                match n {
+                    Ok(n) => {
+                        for _ in 0..n {
+                            match debugger.step() {
+                                Ok(Some(trace)) => writeln!(output, "{}", trace)?,
+                                Ok(None) => {
+                                    writeln!(output, "{:?}", debugger.stopped())?;
+                                    break;
+                                }
+                                Err(err) => {
+                                    writeln!(output, "error: {}", err)?;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(_) => writeln!(output, "error: invalid count {}", args)?,
+                }
+            }
+            "c" | "continue" => {
+                let start = debugger.trace().len();
+                let result = debugger.run();
+                for trace in &debugger.trace()[start..] {
+                    writeln!(output, "{}", trace)?;
+                }
+                match result {
+                    Ok(Stop::Break(bp)) => writeln!(output, "breakpoint: {}", bp)?,
+                    Ok(stop) => writeln!(output, "{:?}", stop)?,
+                    Err(err) => writeln!(output, "error: {}", err)?,
+                }
+            }
+            "i" | "input" => match args.split_whitespace().map(str::parse).collect() {
+                Ok(values) => debugger.feed::<Vec<i64>>(values),
+                Err(_) => writeln!(output, "error: invalid input {}", args)?,
+            },
+            "b" | "break" => match args.parse() {
+                Ok(bp) => debugger.add_breakpoint(bp),
+                Err(err) => writeln!(output, "error: {}", err)?,
+            },
+            "d" | "delete" => debugger.clear_breakpoints(),
+            "r" | "regs" => {
+                let [w, x, y, z] = debugger.registers();
+                writeln!(
+                    output,
+                    "pc={} w={} x={} y={} z={}",
+                    debugger.pc(),
+                    w,
+                    x,
+                    y,
+                    z
+                )?
             }
+            "t" | "trace" => {
+                for trace in debugger.trace() {
+                    writeln!(output, "{}", trace)?;
+                }
+            }
+            "reset" => debugger.reset(),
+            "q" | "quit" => return Ok(()),
+            "h" | "help" => writeln!(output, "{}", HELP)?,
+            _ => writeln!(output, "error: unrecognized command {}\n{}", cmd, HELP)?,
+        }
+        write!(output, "(alu) ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// The two digits tied together by a matching push/pop pair of blocks
+/// must satisfy `digits[hi] == digits[lo] + offset`.
+pub struct Constraint {
+    /// Index of the digit read by the pushing block.
+    pub lo: usize,
+    /// Index of the digit read by the popping block.
+    pub hi: usize,
+    /// Difference between the two digits.
+    pub offset: i64,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// MONAD reads one digit per 18-instruction block. Each block either
+/// pushes `digit + add_y` onto a base-26 stack in Z (`div z 1`), or
+/// pops the top of the stack (`div z 26`) and only avoids pushing again
+/// if `top + add_x == digit`. Z ends up zero exactly when every pop
+/// block's condition holds, so the valid model numbers are described
+/// completely by a set of pairwise digit constraints.
+pub struct Monad {
+    digits: usize,
+    constraints: Vec<Constraint>,
+}
+
+/// Length of each `inp`-delimited block in MONAD.
+const BLOCK: usize = 18;
+
+impl Monad {
+    /// Recognize the MONAD block structure and extract the constraints
+    /// between digits. Returns `None` if the program doesn't match.
+    pub fn analyze(program: &[Op]) -> Option<Self> {
+        let blocks = program.chunks_exact(BLOCK);
+        if program.is_empty() || !blocks.remainder().is_empty() {
+            return None;
+        }
+
+        let mut stack = vec![];
+        let mut constraints = vec![];
+        for (i, block) in blocks.enumerate() {
+            let (div, add_x, add_y) = Self::block(block)?;
+            match div {
+                1 => stack.push((i, add_y)),
+                26 => {
+                    let (lo, add_y) = stack.pop()?;
+                    constraints.push(Constraint {
+                        lo,
+                        hi: i,
+                        offset: add_y + add_x,
+                    });
+                }
+                _ => return None,
+            }
+        }
+
+        if !stack.is_empty() {
+            return None;
         }
-        *pc += 1;
+
+        Some(Self {
+            digits: program.len() / BLOCK,
+            constraints,
+        })
+    }
+
+    /// Extract `div z {div}`, `add x {add_x}` and `add y {add_y}` from
+    /// a single block, checking that every other instruction matches.
+    fn block(block: &[Op]) -> Option<(i64, i64, i64)> {
+        use Op::*;
+        use Reg::*;
+        use Val::Imm;
+        let (div, add_x, add_y) = match (block[4], block[5], block[15]) {
+            (Div(Z, Imm(div)), Add(X, Imm(add_x)), Add(Y, Imm(add_y))) => (div, add_x, add_y),
+            _ => return None,
+        };
+        let expected = format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\n\
+             add y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y",
+            div, add_x, add_y
+        );
+        let matches = expected
+            .lines()
+            .zip(block)
+            .all(|(line, op)| line.parse::<Op>().as_ref() == Ok(op));
+        matches.then_some((div, add_x, add_y))
+    }
+
+    /// Constraints between pairs of digits.
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Pick digits in order of preference from `input` so that every
+    /// constraint is satisfied. Since each digit is part of exactly one
+    /// constraint, the leftmost digit of each pair decides the number.
+    pub fn solve(&self, input: &[i64]) -> Option<i64> {
+        let mut digits = vec![0; self.digits];
+        for c in self.constraints.iter() {
+            let lo = input
+                .iter()
+                .copied()
+                .find(|&d| input.contains(&(d + c.offset)))?;
+            digits[c.lo] = lo;
+            digits[c.hi] = lo + c.offset;
+        }
+        Some(digits.iter().fold(0, |acc, d| acc * 10 + d))
     }
-    Progress::Done
 }
 
 /// Find the first input (as a decimal number) that causes the program
 /// to store zero in the Z register.
-fn solve(program: &[Op], input: &[i64]) -> Option<i64> {
+fn solve(program: &Compiled, input: &[i64]) -> Option<i64> {
     let mut seen: HashMap<(usize, i64), Option<i64>> = HashMap::new();
     search(0, program, 0, [0; 4], input, &mut seen)
 }
@@ -181,7 +905,7 @@ fn solve(program: &[Op], input: &[i64]) -> Option<i64> {
 /// to store zero in the Z register.
 fn search(
     acc: i64,
-    program: &[Op],
+    program: &Compiled,
     pc: usize,
     registers: [i64; 4],
     input: &[i64],
@@ -196,15 +920,16 @@ fn search(
         let mut pc = pc;
         let mut registers = registers;
 
-        match step(program, &mut pc, &mut registers, i) {
-            Progress::Done => {
+        match program.step(&mut pc, &mut registers, i) {
+            Err(_) => continue,
+            Ok(Progress::Done) => {
                 if registers[z] == 0 {
                     let result = Some(acc * 10 + i);
                     seen.insert((pc, registers[z]), result);
                     return result;
                 }
             }
-            Progress::More => {
+            Ok(Progress::More) => {
                 if let result @ Some(_) = search(acc * 10 + i, program, pc, registers, input, seen)
                 {
                     seen.insert((pc, registers[z]), result);
@@ -218,7 +943,128 @@ fn search(
     None
 }
 
-// Warning, even in release mode, it takes about 10 seconds to run
-// both tests. In debug mode, part 2 takes 3 seconds, but part 1
-// takes 2 minutes :(
+// Both parts are solved by `Monad` analysis. If the program doesn't
+// match, the fallback search takes about 10 seconds in release mode
+// and 2 minutes in debug mode :(
 check!(part 1 = 53999995829399, part 2 = 11721151118175);
+bench!(part 1, part 2);
+
+#[cfg(test)]
+mod checks {
+    use super::*;
+
+    fn program(text: &str) -> Vec<Op> {
+        text.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn monad_constraints() {
+        let monad = Monad::analyze(&parse(&crate::check::parse_lines(tests::INPUT))).unwrap();
+        let constraints = monad.constraints();
+        assert_eq!(constraints.len(), 7);
+        let digits = constraints
+            .iter()
+            .flat_map(|c| [c.lo, c.hi])
+            .sorted()
+            .collect_vec();
+        assert_eq!(digits, (0..14).collect_vec());
+        assert!(constraints
+            .iter()
+            .all(|c| c.lo < c.hi && c.offset.abs() < 9));
+    }
+
+    #[test]
+    fn fallback_search() {
+        // z = second - first - 1, so the second digit must be one more
+        // than the first. This isn't MONAD, so the search has to find it.
+        let program = program("inp w\nadd z w\nmul z -1\ninp w\nadd z w\nadd z -1");
+        assert_eq!(Monad::analyze(&program), None);
+        assert_eq!(
+            model_number(&program, &(1..=9).rev().collect_vec()),
+            Some(89)
+        );
+        assert_eq!(model_number(&program, &(1..=9).collect_vec()), Some(12));
+    }
+
+    #[test]
+    fn validate_rejects() {
+        let program = parse(&crate::check::parse_lines(tests::INPUT));
+        assert!(validate(&program, 53999995829399));
+        assert!(!validate(&program, -53999995829399));
+        assert!(!validate(&program, 0));
+        assert!(!validate(&program, 53999905829399));
+        assert!(!validate(&program, 5399999582939));
+        assert!(!validate(&program, 539999958293991));
+    }
+
+    #[test]
+    fn breakpoints() {
+        let program = program("inp w\nadd x w\nmul x 2\nadd z x");
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(Breakpoint::Pc(0));
+        debugger.add_breakpoint(Breakpoint::Pc(2));
+        debugger.add_breakpoint("z > 5".parse().unwrap());
+
+        assert_eq!(debugger.run(), Ok(Stop::Break(Breakpoint::Pc(0))));
+        assert_eq!(debugger.run(), Ok(Stop::Input));
+        debugger.feed(vec![3]);
+        assert_eq!(debugger.run(), Ok(Stop::Break(Breakpoint::Pc(2))));
+        assert_eq!(debugger.registers(), [3, 3, 0, 0]);
+        assert_eq!(
+            debugger.run(),
+            Ok(Stop::Break(Breakpoint::Reg(Reg::Z, Cmp::Gt, 5)))
+        );
+        assert_eq!(debugger.pc(), 4);
+        assert_eq!(debugger.run(), Ok(Stop::Halt));
+        assert_eq!(debugger.trace().len(), 4);
+
+        debugger.reset();
+        assert_eq!(debugger.run(), Ok(Stop::Break(Breakpoint::Pc(0))));
+        assert!(debugger.trace().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        fn run(text: &str) -> Result<Stop, AluError> {
+            Debugger::new(&program(text)).run()
+        }
+        assert_eq!(
+            run("add x 1\ndiv x 0"),
+            Err(AluError::DivideByZero { pc: 1 })
+        );
+        assert_eq!(run("mod x 0"), Err(AluError::ModuloByZero { pc: 0 }));
+        assert_eq!(
+            run("add x -7\nmod x 2"),
+            Err(AluError::NegativeModulo {
+                pc: 1,
+                lhs: -7,
+                rhs: 2
+            })
+        );
+        let min = format!("add x {}\nadd x -1\n", i64::MIN + 1);
+        assert_eq!(
+            run(&format!("{}div x -1", min)),
+            Err(AluError::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            run(&format!("{}add x -1", min)),
+            Err(AluError::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            run(&format!("{}mul x 2", min)),
+            Err(AluError::Overflow { pc: 2 })
+        );
+        let mut compiled_registers = [0; 4];
+        let program = program(&format!("inp w\n{}mul x w", min));
+        let result = Compiled::new(&program).step(&mut 0, &mut compiled_registers, 3);
+        assert!(matches!(result, Err(AluError::Overflow { pc: 3 })));
+        assert_eq!(
+            run("add x 7\nmod x -2"),
+            Err(AluError::NegativeModulo {
+                pc: 1,
+                lhs: 7,
+                rhs: -2
+            })
+        );
+    }
+}
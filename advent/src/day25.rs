@@ -1,26 +1,24 @@
+use crate::grid::Grid;
+use std::fmt::{self, Display, Formatter};
+
 /// Find the step where no more moves are possible.
 pub fn part1(lines: &[String]) -> usize {
     solve(parse(lines))
 }
 
-/// Parse into a grid.
-fn parse(lines: &[String]) -> Vec<Vec<Facing>> {
-    lines
-        .iter()
-        .map(|line| {
-            line.chars()
-                .map(|c| match c {
-                    '>' => Facing::East,
-                    'v' => Facing::South,
-                    _ => Facing::Empty,
-                })
-                .collect()
-        })
-        .collect()
+/// Parse into a grid that wraps around at the edges.
+fn parse(lines: &[String]) -> Grid<Facing> {
+    Grid::parse(lines, |c| match c {
+        '>' => Some(Facing::East),
+        'v' => Some(Facing::South),
+        _ => Some(Facing::Empty),
+    })
+    .unwrap()
+    .wrapping(true)
 }
 
 /// Iterate the map until no more moves are possible.
-fn solve(mut map: Vec<Vec<Facing>>) -> usize {
+fn solve(mut map: Grid<Facing>) -> usize {
     let mut steps = 0;
     loop {
         let east = step(&mut map, Facing::East, (0, 1));
@@ -35,25 +33,24 @@ fn solve(mut map: Vec<Vec<Facing>>) -> usize {
 
 /// Attempt to move each matching item as specified, returning whether
 /// any moves took place.
-fn step(map: &mut Vec<Vec<Facing>>, direction: Facing, change: (usize, usize)) -> bool {
-    let (dy, dx) = change;
-    let rows = map.len();
-    let cols = map[0].len();
+fn step(map: &mut Grid<Facing>, direction: Facing, change: (isize, isize)) -> bool {
     let mut changes = Vec::new();
 
-    for (i, row) in map.iter().enumerate() {
-        for (j, &facing) in row.iter().enumerate() {
-            let (r, c) = ((i + dy) % rows, (j + dx) % cols);
-            if facing == direction && map[r][c] == Facing::Empty {
-                changes.push(((i, j), (r, c)));
+    for (point, &facing) in map.iter() {
+        if facing != direction {
+            continue;
+        }
+        if let Some(next) = map.offset(point, change) {
+            if map[next] == Facing::Empty {
+                changes.push((point, next));
             }
         }
     }
 
-    let changed = changes.len() > 0;
-    for ((i, j), (r, c)) in changes.into_iter() {
-        map[r][c] = direction;
-        map[i][j] = Facing::Empty;
+    let changed = !changes.is_empty();
+    for (point, next) in changes.into_iter() {
+        map[next] = direction;
+        map[point] = Facing::Empty;
     }
     changed
 }
@@ -69,5 +66,16 @@ pub enum Facing {
     South,
 }
 
+impl Display for Facing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let c = match self {
+            Facing::Empty => '.',
+            Facing::East => '>',
+            Facing::South => 'v',
+        };
+        write!(f, "{}", c)
+    }
+}
+
 check!(ex 1 = 58, part 1 = 532);
 bench!(part 1);
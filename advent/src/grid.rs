@@ -0,0 +1,301 @@
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+/// Position in a grid as _(row, column)_.
+pub type Pos = (usize, usize);
+
+/// Offsets to each neighbor in the cardinal directions.
+pub const CARDINAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (1, 0), (0, 1)];
+
+/// Offsets to each neighbor in the cardinal and diagonal directions.
+pub const ALL: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+/// Rectangular 2D grid stored in row-major order. Optionally wraps
+/// around at the edges, so the grid behaves like a torus.
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    wrap: bool,
+}
+
+impl<T> Grid<T> {
+    /// Create a grid from its cells in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of cells doesn't match the dimensions.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(width * height, cells.len());
+        Self {
+            width,
+            height,
+            cells,
+            wrap: false,
+        }
+    }
+
+    /// Create a grid with every cell set to the same value.
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    /// Parse a character map, one row per line. Returns `None` if any
+    /// character is rejected or the rows aren't all the same length.
+    pub fn parse<F>(lines: &[String], mut cell: F) -> Option<Self>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let height = lines.len();
+        let width = lines.first()?.chars().count();
+        let mut cells = Vec::with_capacity(width * height);
+        for line in lines {
+            let row = line.chars().map(&mut cell).collect::<Option<Vec<_>>>()?;
+            if row.len() != width {
+                return None;
+            }
+            cells.extend(row);
+        }
+        Some(Self::new(width, height, cells))
+    }
+
+    /// Make the grid wrap around at its edges.
+    pub fn wrapping(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Convert signed coordinates to a position in the grid, wrapping
+    /// around if the grid is toroidal. Returns `None` if the
+    /// coordinates are out of bounds.
+    pub fn checked(&self, row: isize, col: isize) -> Option<Pos> {
+        let (height, width) = (self.height as isize, self.width as isize);
+        if self.wrap && height > 0 && width > 0 {
+            return Some((
+                row.rem_euclid(height) as usize,
+                col.rem_euclid(width) as usize,
+            ));
+        }
+        if (0..height).contains(&row) && (0..width).contains(&col) {
+            return Some((row as usize, col as usize));
+        }
+        None
+    }
+
+    /// Is the position within the grid?
+    pub fn contains(&self, (row, col): Pos) -> bool {
+        row < self.height && col < self.width
+    }
+
+    /// Move from the position by the offset, if that stays in bounds.
+    pub fn offset(&self, (row, col): Pos, (dy, dx): (isize, isize)) -> Option<Pos> {
+        self.checked(row as isize + dy, col as isize + dx)
+    }
+
+    /// Neighbors in the cardinal directions.
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        CARDINAL
+            .iter()
+            .filter_map(move |&delta| self.offset(pos, delta))
+    }
+
+    /// Neighbors in the cardinal and diagonal directions.
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ALL.iter().filter_map(move |&delta| self.offset(pos, delta))
+    }
+
+    /// Get the cell at the position, if it's in bounds.
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) {
+            return Some(&self[pos]);
+        }
+        None
+    }
+
+    /// Get a mutable reference to the cell at the position, if it's in
+    /// bounds.
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            return Some(&mut self[pos]);
+        }
+        None
+    }
+
+    /// Every position in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        (0..self.height).cartesian_product(0..self.width)
+    }
+
+    /// Every position and cell in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Every cell in row-major order.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Apply the function to every cell.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+            wrap: self.wrap,
+        }
+    }
+
+    /// Index of the position in the row-major cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is out of bounds, even when the flat
+    /// index would land in another row.
+    fn flat(&self, (row, col): Pos) -> usize {
+        assert!(
+            row < self.height && col < self.width,
+            "position ({}, {}) out of bounds for {}x{} grid",
+            row,
+            col,
+            self.width,
+            self.height
+        );
+        row * self.width + col
+    }
+}
+
+impl Grid<u8> {
+    /// Parse a map of decimal digits.
+    pub fn digits(lines: &[String]) -> Option<Self> {
+        Self::parse(lines, |c| c.to_digit(10).map(|d| d as u8))
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        &self.cells[self.flat(pos)]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let i = self.flat(pos);
+        &mut self.cells[i]
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        for (i, row) in self.cells.chunks(self.width.max(1)).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::parse_lines;
+
+    fn sample() -> Grid<u8> {
+        Grid::digits(&parse_lines("123\n456")).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.cells(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(grid.to_string(), "123\n456");
+
+        assert_eq!(Grid::digits(&parse_lines("123\n45")), None);
+        assert_eq!(Grid::digits(&parse_lines("12\n345")), None);
+        assert_eq!(Grid::digits(&parse_lines("123\n4x6")), None);
+        assert_eq!(Grid::digits(&[]), None);
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = sample();
+        let around = |pos, all: bool| -> Vec<Pos> {
+            if all {
+                grid.neighbors8(pos).sorted().collect()
+            } else {
+                grid.neighbors4(pos).sorted().collect()
+            }
+        };
+        assert_eq!(around((0, 0), false), vec![(0, 1), (1, 0)]);
+        assert_eq!(around((0, 0), true), vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(around((1, 2), false), vec![(0, 2), (1, 1)]);
+        assert_eq!(around((1, 1), false), vec![(0, 1), (1, 0), (1, 2)]);
+        assert_eq!(
+            around((1, 1), true),
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 2)]
+        );
+
+        let torus = sample().wrapping(true);
+        assert_eq!(
+            torus.neighbors4((0, 0)).sorted().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 0), (1, 0)]
+        );
+    }
+
+    #[test]
+    fn map() {
+        let grid = sample().wrapping(true).map(|&d| d * 10);
+        assert_eq!(grid.cells(), &[10, 20, 30, 40, 50, 60]);
+        assert_eq!(grid[(1, 2)], 60);
+        assert_eq!(grid.offset((0, 0), (0, -1)), Some((0, 2)));
+    }
+
+    #[test]
+    fn get() {
+        let mut grid = sample();
+        assert_eq!(grid.get((1, 0)), Some(&4));
+        assert_eq!(grid.get((0, 3)), None);
+        assert_eq!(grid.get((2, 0)), None);
+        *grid.get_mut((0, 1)).unwrap() = 9;
+        assert_eq!(grid[(0, 1)], 9);
+        assert!(grid.get_mut((5, 5)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn index_past_row() {
+        // (0, 3) would alias (1, 0) without the column check.
+        let _ = sample()[(0, 3)];
+    }
+}
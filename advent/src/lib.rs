@@ -16,6 +16,24 @@ pub mod check;
 /// Macros for generating benchmarks
 pub mod bench;
 
+/// Grid shared by solutions that work on 2D maps
+pub mod grid;
+
+/// Shortest-path searches shared by solutions
+pub mod search;
+
+/// Letter recognition for solutions that draw text
+pub mod ocr;
+
+/// Bracket matching with configurable pairs
+pub mod brackets;
+
+/// Square matrices of counts shared by solutions
+pub mod matrix;
+
+/// Axis-aligned boxes in any number of dimensions
+pub mod cuboid;
+
 /// Solutions for day 1
 pub mod day01;
 /// Solutions for day 2
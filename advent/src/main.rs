@@ -1,8 +1,21 @@
+use advent::day05::Format;
 use nix::sys::wait::waitpid;
 use nix::unistd::{fork, ForkResult, Pid};
 use std::process::{exit, Command};
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["alu", path] => exit(debug_alu(path).unwrap_or(1)),
+        ["burrow", path] => exit(replay_burrow(path, false).unwrap_or(1)),
+        ["burrow", path, "--unfold"] => exit(replay_burrow(path, true).unwrap_or(1)),
+        ["vents", path] => exit(draw_vents(path, Format::Text).unwrap_or(1)),
+        ["vents", path, "--pgm"] => exit(draw_vents(path, Format::Pgm).unwrap_or(1)),
+        ["vents", path, "--ppm"] => exit(draw_vents(path, Format::Ppm).unwrap_or(1)),
+        _ => {}
+    }
+
     let forked = unsafe { fork() };
     let code = match forked.expect("failed to fork process") {
         ForkResult::Parent { child } => wait_on(child),
@@ -20,3 +33,37 @@ fn run_tests() -> Option<i32> {
     let status = child.wait().ok()?;
     status.code()
 }
+
+fn debug_alu(path: &str) -> Option<i32> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let program = text.lines().map(str::parse).collect::<Result<Vec<_>, _>>();
+    let program = program.map_err(|err| eprintln!("{}", err)).ok()?;
+    let stdin = std::io::stdin();
+    advent::day24::repl(&program, stdin.lock(), std::io::stdout()).ok()?;
+    Some(0)
+}
+
+fn replay_burrow(path: &str, unfold: bool) -> Option<i32> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    if unfold {
+        lines = advent::day23::UNFOLD.apply(&lines);
+    }
+    let (burrow, state) = advent::day23::Burrow::parse(&lines)
+        .map_err(|err| eprintln!("{}", err))
+        .ok()?;
+    let (_cost, path) = burrow.solve(state).or_else(|| {
+        eprintln!("the amphipods can't be organized");
+        None
+    })?;
+    burrow.replay(&path, std::io::stdout()).ok()?;
+    Some(0)
+}
+
+fn draw_vents(path: &str, format: Format) -> Option<i32> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let lines: Vec<advent::day05::Line> = text.lines().flat_map(str::parse).collect();
+    let density = advent::day05::Density::new(&lines);
+    density.write(&lines, format, std::io::stdout()).ok()?;
+    Some(0)
+}
@@ -0,0 +1,157 @@
+use std::num::NonZeroU64;
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+/// How to combine counts that may grow without bound.
+pub enum Arithmetic {
+    /// Fail on overflow.
+    Checked,
+    /// Work modulo a number.
+    Modulo(NonZeroU64),
+}
+
+impl Arithmetic {
+    /// Add two counts. Returns `None` on overflow.
+    pub fn add(self, lhs: u64, rhs: u64) -> Option<u64> {
+        match self {
+            Arithmetic::Checked => lhs.checked_add(rhs),
+            Arithmetic::Modulo(m) => Some(((lhs as u128 + rhs as u128) % m.get() as u128) as u64),
+        }
+    }
+
+    /// Multiply two counts. Returns `None` on overflow.
+    pub fn mul(self, lhs: u64, rhs: u64) -> Option<u64> {
+        match self {
+            Arithmetic::Checked => lhs.checked_mul(rhs),
+            Arithmetic::Modulo(m) => Some(((lhs as u128 * rhs as u128) % m.get() as u128) as u64),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Square matrix of counts.
+pub struct Matrix {
+    n: usize,
+    cells: Vec<u64>,
+}
+
+impl Matrix {
+    /// Create an _n × n_ matrix of zeros.
+    pub fn zero(n: usize) -> Self {
+        Self {
+            n,
+            cells: vec![0; n * n],
+        }
+    }
+
+    /// Create the _n × n_ identity matrix.
+    pub fn identity(n: usize) -> Self {
+        let mut matrix = Self::zero(n);
+        for i in 0..n {
+            matrix.cells[i * n + i] = 1;
+        }
+        matrix
+    }
+
+    /// Get the cell at _(row, column)_.
+    pub fn get(&self, row: usize, col: usize) -> u64 {
+        self.cells[row * self.n + col]
+    }
+
+    /// Set the cell at _(row, column)_.
+    pub fn set(&mut self, row: usize, col: usize, value: u64) {
+        self.cells[row * self.n + col] = value;
+    }
+
+    /// Multiply two matrices. Returns `None` on overflow.
+    pub fn mul(&self, other: &Self, arithmetic: Arithmetic) -> Option<Self> {
+        let n = self.n;
+        let mut product = Self::zero(n);
+        for row in 0..n {
+            for k in 0..n {
+                let lhs = self.get(row, k);
+                if lhs == 0 {
+                    continue;
+                }
+                for col in 0..n {
+                    let term = arithmetic.mul(lhs, other.get(k, col))?;
+                    let cell = &mut product.cells[row * n + col];
+                    *cell = arithmetic.add(*cell, term)?;
+                }
+            }
+        }
+        Some(product)
+    }
+
+    /// Raise the matrix to a power by repeated squaring. Returns `None`
+    /// on overflow.
+    pub fn pow(&self, mut exp: u64, arithmetic: Arithmetic) -> Option<Self> {
+        let mut result = Self::identity(self.n);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base, arithmetic)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base, arithmetic)?;
+            }
+        }
+        Some(result)
+    }
+
+    /// Multiply the matrix by a column vector. Returns `None` on
+    /// overflow.
+    pub fn apply(&self, vector: &[u64], arithmetic: Arithmetic) -> Option<Vec<u64>> {
+        (0..self.n)
+            .map(|row| {
+                vector.iter().enumerate().try_fold(0, |sum, (col, &x)| {
+                    arithmetic.add(sum, arithmetic.mul(self.get(row, col), x)?)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Matrix taking `[F(n + 1), F(n)]` to `[F(n + 2), F(n + 1)]`.
+    fn fibonacci() -> Matrix {
+        let mut matrix = Matrix::zero(2);
+        matrix.set(0, 0, 1);
+        matrix.set(0, 1, 1);
+        matrix.set(1, 0, 1);
+        matrix
+    }
+
+    #[test]
+    fn pow() {
+        // The top left of the power `n - 1` is F(n), and no entry of it or
+        // of the squares on the way is any larger, so overflow only means
+        // F(n) itself doesn't fit.
+        let fib = |n: u64, arithmetic| match n {
+            0 => Some(0),
+            _ => Some(fibonacci().pow(n - 1, arithmetic)?.get(0, 0)),
+        };
+        assert_eq!(fib(0, Arithmetic::Checked), Some(0));
+        assert_eq!(fib(1, Arithmetic::Checked), Some(1));
+        assert_eq!(fib(10, Arithmetic::Checked), Some(55));
+        assert_eq!(fib(93, Arithmetic::Checked), Some(12200160415121876738));
+        assert_eq!(fib(94, Arithmetic::Checked), None);
+
+        let modulo = Arithmetic::Modulo(NonZeroU64::new(1000).unwrap());
+        assert_eq!(fib(93, modulo), Some(738));
+        assert_eq!(fib(94, modulo), Some(167));
+        assert_eq!(fib(10u64.pow(18), modulo), Some(875));
+    }
+
+    #[test]
+    fn apply() {
+        let matrix = fibonacci();
+        assert_eq!(matrix.apply(&[3, 2], Arithmetic::Checked), Some(vec![5, 3]));
+        assert_eq!(matrix.apply(&[u64::MAX, 1], Arithmetic::Checked), None);
+        let modulo = Arithmetic::Modulo(NonZeroU64::new(7).unwrap());
+        assert_eq!(matrix.apply(&[u64::MAX, 1], modulo), Some(vec![2, 1]));
+    }
+}
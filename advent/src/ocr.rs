@@ -0,0 +1,179 @@
+use crate::grid::{Grid, Pos};
+use std::fmt::{self, Display, Formatter};
+
+/// Width of a glyph in columns.
+pub const WIDTH: usize = 4;
+
+/// Height of a glyph in rows.
+pub const HEIGHT: usize = 6;
+
+/// Columns from the start of one glyph to the start of the next.
+const STRIDE: usize = WIDTH + 1;
+
+/// Letters in the 4×6 font, drawn with `#` for lit pixels.
+const FONT: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Text that couldn't be completely read.
+pub enum Unreadable {
+    /// The text isn't exactly one glyph tall.
+    Height(usize),
+    /// Some glyphs didn't match any letter.
+    Glyphs {
+        /// Everything that was read, with `?` for each unknown glyph.
+        text: String,
+        /// Leftmost column of each unknown glyph.
+        columns: Vec<usize>,
+    },
+}
+
+impl Display for Unreadable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Unreadable::Height(height) => {
+                write!(f, "text is {} rows tall, not {}", height, HEIGHT)
+            }
+            Unreadable::Glyphs { text, columns } => {
+                write!(
+                    f,
+                    "read {:?} but couldn't recognize glyphs at columns ",
+                    text
+                )?;
+                for (i, column) in columns.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", column)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Read letters from a bitmap exactly one glyph tall. Glyphs start
+/// every 5 columns from the left edge, and columns past the right edge
+/// are treated as blank.
+pub fn read(bitmap: &Grid<bool>) -> Result<String, Unreadable> {
+    if bitmap.height() != HEIGHT {
+        return Err(Unreadable::Height(bitmap.height()));
+    }
+
+    let mut text = String::new();
+    let mut columns = vec![];
+    for left in (0..bitmap.width()).step_by(STRIDE) {
+        let lit = |row: usize, col: usize| bitmap.get((row, left + col)) == Some(&true);
+        let letter = FONT.iter().find(|(_, glyph)| {
+            glyph.iter().enumerate().all(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .all(|(col, c)| (c == '#') == lit(row, col))
+            })
+        });
+        match letter {
+            Some(&(letter, _)) => text.push(letter),
+            None => {
+                text.push('?');
+                columns.push(left);
+            }
+        }
+    }
+
+    if columns.is_empty() {
+        return Ok(text);
+    }
+    Err(Unreadable::Glyphs { text, columns })
+}
+
+/// Read letters from lit points given as _(row, column)_, measured from
+/// the top-left corner of the first glyph.
+pub fn read_points<I: IntoIterator<Item = Pos>>(points: I) -> Result<String, Unreadable> {
+    let points = points.into_iter().collect::<Vec<_>>();
+    let height = points.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+    let width = points.iter().map(|&(_, col)| col + 1).max().unwrap_or(0);
+    let mut bitmap = Grid::filled(width, height, false);
+    for point in points {
+        bitmap[point] = true;
+    }
+    read(&bitmap)
+}
+
+/// Read letters drawn as text, where `█` and `#` are lit and anything
+/// else is blank.
+pub fn read_text(text: &str) -> Result<String, Unreadable> {
+    let points = text.lines().enumerate().flat_map(|(row, line)| {
+        line.chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '█' || c == '#')
+            .map(move |(col, _)| (row, col))
+    });
+    read_points(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `HI` followed by a glyph that isn't in the font.
+    const TEXT: &str = "\
+#..#..###.##
+#..#...#..##
+####...#....
+#..#...#....
+#..#...#....
+#..#..###...";
+
+    #[test]
+    fn read_letters() {
+        let lines = TEXT
+            .lines()
+            .map(|line| line[..9].to_string())
+            .collect::<Vec<_>>();
+        let bitmap = Grid::parse(&lines, |c| Some(c == '#')).unwrap();
+        assert_eq!(read(&bitmap), Ok("HI".to_string()));
+        assert_eq!(read_text(&lines.join("\n")), Ok("HI".to_string()));
+        assert_eq!(
+            read_text(&lines.join("\n").replace('#', "█")),
+            Ok("HI".to_string())
+        );
+    }
+
+    #[test]
+    fn unrecognized_glyphs() {
+        let err = read_text(TEXT).unwrap_err();
+        let expected = Unreadable::Glyphs {
+            text: "HI?".to_string(),
+            columns: vec![10],
+        };
+        assert_eq!(err, expected);
+        assert_eq!(
+            err.to_string(),
+            "read \"HI?\" but couldn't recognize glyphs at columns 10"
+        );
+    }
+
+    #[test]
+    fn wrong_height() {
+        assert_eq!(read_text(""), Err(Unreadable::Height(0)));
+        assert_eq!(read_text("#\n#\n#"), Err(Unreadable::Height(3)));
+        assert_eq!(read_points(vec![(6, 0)]), Err(Unreadable::Height(7)));
+    }
+}
@@ -0,0 +1,196 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// A search space whose states have weighted successors.
+pub trait Search {
+    /// Node in the search space.
+    type State: Clone + Eq + Hash;
+
+    /// States reachable in one move along with the cost of each move.
+    fn successors(&self, state: &Self::State) -> Vec<(usize, Self::State)>;
+
+    /// Is the search finished once it reaches this state?
+    fn goal(&self, state: &Self::State) -> bool;
+
+    /// Lower bound on the cost of reaching a goal from this state. Used
+    /// by `astar`, and must never overestimate.
+    fn estimate(&self, _state: &Self::State) -> usize {
+        0
+    }
+}
+
+/// Dijkstra's algorithm to find the cheapest path from the start to a
+/// goal. Returns the cost along with every state on the path, or `None`
+/// if no goal is reachable.
+pub fn dijkstra<S: Search>(search: &S, start: S::State) -> Option<(usize, Vec<S::State>)> {
+    best_first(search, start, |_| 0)
+}
+
+/// A* search to find the cheapest path from the start to a goal using
+/// `Search::estimate` as the heuristic. Returns the cost along with
+/// every state on the path, or `None` if no goal is reachable.
+pub fn astar<S: Search>(search: &S, start: S::State) -> Option<(usize, Vec<S::State>)> {
+    best_first(search, start, |state| search.estimate(state))
+}
+
+/// Breadth-first search to find the path from the start to a goal with
+/// the fewest moves, ignoring their costs.
+pub fn bfs<S: Search>(search: &S, start: S::State) -> Option<Vec<S::State>> {
+    let mut prev = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start.clone());
+    queue.push_back(start);
+
+    while let Some(state) = queue.pop_front() {
+        if search.goal(&state) {
+            return Some(path(&prev, state));
+        }
+
+        for (_, other) in search.successors(&state) {
+            if seen.insert(other.clone()) {
+                prev.insert(other.clone(), state.clone());
+                queue.push_back(other);
+            }
+        }
+    }
+
+    None
+}
+
+/// Expand the state with the smallest cost plus estimate first.
+fn best_first<S, F>(search: &S, start: S::State, estimate: F) -> Option<(usize, Vec<S::State>)>
+where
+    S: Search,
+    F: Fn(&S::State) -> usize,
+{
+    let mut seen = HashMap::new();
+    let mut prev = HashMap::new();
+
+    // Minimum-cost priority queue. BinaryHeap is a max-heap, so use
+    // Reverse to flip comparisons.
+    let mut queue = BinaryHeap::new();
+    seen.insert(start.clone(), 0);
+    queue.push((Reverse(estimate(&start)), Opaque((0, start))));
+
+    while let Some((_, Opaque((cost, state)))) = queue.pop() {
+        if cost > seen[&state] {
+            continue;
+        }
+
+        if search.goal(&state) {
+            return Some((cost, path(&prev, state)));
+        }
+
+        for (new_cost, other) in search.successors(&state) {
+            let total_cost = new_cost + cost;
+            let previous_cost = seen.entry(other.clone()).or_insert(usize::MAX);
+            if total_cost < *previous_cost {
+                *previous_cost = total_cost;
+                let priority = total_cost + estimate(&other);
+                prev.insert(other.clone(), state.clone());
+                queue.push((Reverse(priority), Opaque((total_cost, other))));
+            }
+        }
+    }
+
+    None
+}
+
+/// Follow each state back to the start, returning the states in the
+/// order they were visited.
+pub fn path<K: Hash + Eq + Clone>(prev: &HashMap<K, K>, mut state: K) -> Vec<K> {
+    let mut path = vec![state.clone()];
+    while let Some(before) = prev.get(&state) {
+        path.push(before.clone());
+        state = before.clone();
+    }
+    path.reverse();
+    path
+}
+
+#[derive(Debug, Copy, Clone)]
+/// A value that does not contribute to a composite value's equality
+/// or ordering. It is always equal to other values of the same type.
+pub struct Opaque<A>(pub A);
+
+impl<A> PartialEq for Opaque<A> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<A> Eq for Opaque<A> {}
+
+impl<A> PartialOrd for Opaque<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A> Ord for Opaque<A> {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Walk along a line of numbered stops, either one stop at a time
+    /// for 5 energy or skipping ahead three stops for 20.
+    struct Line {
+        stops: usize,
+    }
+
+    impl Search for Line {
+        type State = usize;
+
+        fn successors(&self, &stop: &usize) -> Vec<(usize, usize)> {
+            vec![(5, stop + 1), (20, stop + 3)]
+                .into_iter()
+                .filter(|&(_, next)| next < self.stops)
+                .collect()
+        }
+
+        fn goal(&self, &stop: &usize) -> bool {
+            stop == self.stops - 1
+        }
+
+        fn estimate(&self, &stop: &usize) -> usize {
+            5 * (self.stops - 1).saturating_sub(stop)
+        }
+    }
+
+    #[test]
+    fn cheapest_path() {
+        let line = Line { stops: 5 };
+        let expected = Some((20, vec![0, 1, 2, 3, 4]));
+        assert_eq!(dijkstra(&line, 0), expected);
+        assert_eq!(astar(&line, 0), expected);
+    }
+
+    #[test]
+    fn fewest_moves() {
+        let line = Line { stops: 5 };
+        assert_eq!(bfs(&line, 0), Some(vec![0, 1, 4]));
+        assert_eq!(bfs(&line, 4), Some(vec![4]));
+    }
+
+    #[test]
+    fn unreachable() {
+        let line = Line { stops: 5 };
+        assert_eq!(dijkstra(&line, 7), None);
+        assert_eq!(astar(&line, 7), None);
+        assert_eq!(bfs(&line, 7), None);
+    }
+
+    #[test]
+    fn opaque() {
+        assert_eq!(Opaque(1), Opaque(2));
+        assert_eq!(Opaque(1).partial_cmp(&Opaque(2)), Some(Ordering::Equal));
+        assert!((1, Opaque(9)) < (2, Opaque(0)));
+    }
+}